@@ -0,0 +1,52 @@
+// Run with `cargo bench`, and compare inflate backends with
+// `cargo bench --no-default-features --features fs,zlib-ng`.
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use eq_archive::{testing, SharedArchive, WriteOptions};
+
+// A zone's worth of textures
+fn large() -> Vec<u8> {
+    testing::build(
+        &testing::random_files(0, 64, 256 * 1024),
+        WriteOptions::default(),
+    )
+}
+
+fn parse(c: &mut Criterion) {
+    let large = large();
+    c.bench_function("parse", |b| {
+        b.iter(|| eq_archive::load(black_box(&large)).unwrap())
+    });
+}
+
+// Thousands of small files, so reading the directory dominates
+fn directory(c: &mut Criterion) {
+    let many = testing::build(&testing::random_files(1, 4096, 64), WriteOptions::default());
+    c.bench_function("directory", |b| {
+        b.iter(|| eq_archive::load(black_box(&many)).unwrap())
+    });
+}
+
+fn extract(c: &mut Criterion) {
+    let large = large();
+    let archive = eq_archive::load(&large).unwrap();
+    c.bench_function("extract", |b| {
+        b.iter(|| {
+            for file in archive.iter_files() {
+                black_box(file.unwrap());
+            }
+        })
+    });
+    c.bench_function("prefetch", |b| {
+        b.iter(|| {
+            let archive = SharedArchive::new(eq_archive::load(&large).unwrap());
+            archive.prefetch().unwrap();
+            archive
+        })
+    });
+}
+
+criterion_group!(benches, parse, directory, extract);
+criterion_main!(benches);
@@ -0,0 +1,180 @@
+// Mounts an archive as a read-only directory so its files can be browsed in
+// a file manager. Unmount with `fusermount -u <mountpoint>`.
+//
+//     cargo run --example mount --features fuse -- gfaydark.s3d /mnt/gfaydark
+
+use std::env;
+use std::ffi::OsStr;
+use std::process;
+use std::time::{Duration, UNIX_EPOCH};
+
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request,
+};
+use libc::ENOENT;
+
+use eq_archive::{Archive, EntryInfo};
+
+const ROOT: u64 = 1;
+// The archive can't change while it's mounted
+const TTL: Duration = Duration::from_secs(60);
+
+struct ArchiveFs {
+    archive: Archive<'static>,
+    entries: Vec<EntryInfo>,
+}
+
+impl ArchiveFs {
+    // Inodes follow the root, in the order files are stored
+    fn entry(&self, ino: u64) -> Option<&EntryInfo> {
+        let position = ino.checked_sub(ROOT + 1)?;
+        self.entries.get(position as usize)
+    }
+
+    fn attr(&self, ino: u64, kind: FileType, size: u64) -> FileAttr {
+        let mtime = self.archive.timestamp().unwrap_or(UNIX_EPOCH);
+        let (perm, nlink) = match kind {
+            FileType::Directory => (0o555, 2),
+            _ => (0o444, 1),
+        };
+        FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind,
+            perm,
+            nlink,
+            uid: unsafe { libc::getuid() },
+            gid: unsafe { libc::getgid() },
+            rdev: 0,
+            blksize: 512,
+            flags: 0,
+        }
+    }
+
+    fn file_attr(&self, ino: u64) -> Option<FileAttr> {
+        let entry = self.entry(ino)?;
+        Some(self.attr(ino, FileType::RegularFile, entry.uncompressed_size.into()))
+    }
+}
+
+impl Filesystem for ArchiveFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let position = name.to_str().filter(|_| parent == ROOT).and_then(|name| {
+            self.entries
+                .iter()
+                .position(|entry| entry.filename.eq_ignore_ascii_case(name))
+        });
+        match position.and_then(|position| self.file_attr(position as u64 + ROOT + 1)) {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        if ino == ROOT {
+            return reply.attr(&TTL, &self.attr(ROOT, FileType::Directory, 0));
+        }
+        match self.file_attr(ino) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let filename = match self.entry(ino) {
+            Some(entry) => entry.filename.as_str(),
+            None => return reply.error(ENOENT),
+        };
+        // Only the blocks covering the range are inflated
+        match self
+            .archive
+            .read_range(filename, offset as u64, size as usize)
+        {
+            Ok(Some(data)) => reply.data(&data),
+            Ok(None) => reply.error(ENOENT),
+            Err(error) => {
+                eprintln!("{}: {}", filename, error);
+                reply.error(libc::EIO)
+            }
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        if ino != ROOT {
+            return reply.error(ENOENT);
+        }
+        let dots = [
+            (ROOT, FileType::Directory, "."),
+            (ROOT, FileType::Directory, ".."),
+        ];
+        let files = self.entries.iter().enumerate().map(|(position, entry)| {
+            (
+                position as u64 + ROOT + 1,
+                FileType::RegularFile,
+                entry.filename.as_str(),
+            )
+        });
+        // The offset passed back in is the one given with the last entry added
+        for (next, (ino, kind, name)) in dots
+            .iter()
+            .copied()
+            .chain(files)
+            .enumerate()
+            .skip(offset as usize)
+        {
+            if reply.add(ino, next as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (path, mountpoint) = match args.as_slice() {
+        [path, mountpoint] => (path, mountpoint),
+        _ => {
+            eprintln!("Usage: mount <archive> <mountpoint>");
+            process::exit(2);
+        }
+    };
+
+    let archive = eq_archive::read(path).unwrap_or_else(|error| {
+        eprintln!("{}: {}", path, error);
+        process::exit(1);
+    });
+    let entries = archive.entries_info();
+    let options = [
+        MountOption::RO,
+        MountOption::FSName(path.clone()),
+        MountOption::Subtype("eq_archive".to_string()),
+    ];
+    if let Err(error) = fuser::mount2(ArchiveFs { archive, entries }, mountpoint, &options) {
+        eprintln!("{}: {}", mountpoint, error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(archive) = eq_archive::load(data) {
+        archive.verify();
+        archive.entries_info();
+        for filename in archive.filenames() {
+            let _ = archive.get(filename);
+        }
+    }
+});
@@ -0,0 +1,33 @@
+#ifndef EQ_ARCHIVE_H
+#define EQ_ARCHIVE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct EqArchive EqArchive;
+
+/* Returns NULL if the archive can't be read */
+EqArchive *eq_archive_open(const char *path);
+
+/* The returned strings are owned by the archive and valid until it's freed */
+const char *const *eq_archive_filenames(const EqArchive *archive, size_t *count);
+
+/* Returns NULL if the file doesn't exist, free the data with eq_archive_free_data */
+uint8_t *eq_archive_get(const EqArchive *archive, const char *filename, size_t *len);
+
+/* Writes every file out under dest, returns 0 on success and -1 on failure */
+int eq_archive_extract_all(const EqArchive *archive, const char *dest);
+
+void eq_archive_free_data(uint8_t *data, size_t len);
+
+void eq_archive_free(EqArchive *archive);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,119 @@
+"""Python bindings for eq_archive through its C API.
+
+Build the shared library with ``cargo build --release --features ffi`` and
+point ``EQ_ARCHIVE_LIB`` at it, or copy it next to this file.
+
+    with EqArchive.open("gfaydark.s3d") as archive:
+        for filename in archive.filenames():
+            print(filename)
+        wld = archive.get("gfaydark.wld")
+        archive.extract_all("gfaydark")
+"""
+
+import ctypes
+import os
+import sys
+
+__all__ = ["EqArchive", "EqArchiveError"]
+
+
+class EqArchiveError(Exception):
+    pass
+
+
+def _library_name():
+    if sys.platform == "win32":
+        return "eq_archive.dll"
+    if sys.platform == "darwin":
+        return "libeq_archive.dylib"
+    return "libeq_archive.so"
+
+
+def _load():
+    path = os.environ.get("EQ_ARCHIVE_LIB") or os.path.join(
+        os.path.dirname(os.path.abspath(__file__)), _library_name()
+    )
+    lib = ctypes.CDLL(path)
+
+    lib.eq_archive_open.argtypes = [ctypes.c_char_p]
+    lib.eq_archive_open.restype = ctypes.c_void_p
+    lib.eq_archive_filenames.argtypes = [ctypes.c_void_p, ctypes.POINTER(ctypes.c_size_t)]
+    lib.eq_archive_filenames.restype = ctypes.POINTER(ctypes.c_char_p)
+    lib.eq_archive_get.argtypes = [
+        ctypes.c_void_p,
+        ctypes.c_char_p,
+        ctypes.POINTER(ctypes.c_size_t),
+    ]
+    lib.eq_archive_get.restype = ctypes.POINTER(ctypes.c_uint8)
+    lib.eq_archive_extract_all.argtypes = [ctypes.c_void_p, ctypes.c_char_p]
+    lib.eq_archive_extract_all.restype = ctypes.c_int
+    lib.eq_archive_free_data.argtypes = [ctypes.POINTER(ctypes.c_uint8), ctypes.c_size_t]
+    lib.eq_archive_free_data.restype = None
+    lib.eq_archive_free.argtypes = [ctypes.c_void_p]
+    lib.eq_archive_free.restype = None
+    return lib
+
+
+_lib = None
+
+
+def _library():
+    # Loaded on first use so importing the module never fails
+    global _lib
+    if _lib is None:
+        _lib = _load()
+    return _lib
+
+
+class EqArchive:
+    def __init__(self, handle):
+        self._handle = handle
+
+    @classmethod
+    def open(cls, path):
+        handle = _library().eq_archive_open(os.fsencode(path))
+        if not handle:
+            raise EqArchiveError("could not read archive {}".format(path))
+        return cls(handle)
+
+    def filenames(self):
+        count = ctypes.c_size_t()
+        names = _library().eq_archive_filenames(self._require(), ctypes.byref(count))
+        # Names that aren't valid UTF-8 are lossily decoded, like in Rust
+        return [names[i].decode("utf-8", "replace") for i in range(count.value)]
+
+    def get(self, filename):
+        """The contents of a file, or None if the archive doesn't have it."""
+        size = ctypes.c_size_t()
+        data = _library().eq_archive_get(
+            self._require(), filename.encode("utf-8"), ctypes.byref(size)
+        )
+        if not data:
+            return None
+        try:
+            return ctypes.string_at(data, size.value)
+        finally:
+            _library().eq_archive_free_data(data, size.value)
+
+    def extract_all(self, dest):
+        if _library().eq_archive_extract_all(self._require(), os.fsencode(dest)) != 0:
+            raise EqArchiveError("could not extract archive to {}".format(dest))
+
+    def close(self):
+        if self._handle:
+            _library().eq_archive_free(self._handle)
+            self._handle = None
+
+    def _require(self):
+        if not self._handle:
+            raise EqArchiveError("archive is closed")
+        return self._handle
+
+    def __enter__(self):
+        return self
+
+    def __exit__(self, *exc):
+        self.close()
+
+    def __del__(self):
+        self.close()
@@ -0,0 +1,143 @@
+use std::io::{self, Seek, SeekFrom, Write};
+use std::time::SystemTime;
+
+use crate::crc::filename_crc;
+use crate::parser::{DIRECTORY_CRC, HEADER_SIZE};
+use crate::progress::Tracker;
+use crate::writer::{block, directory, to_u32, validate, write_header, write_index};
+use crate::WriteOptions;
+
+struct CurrentFile {
+    name: String,
+    pointer: u32,
+    size: u32,
+    buffer: Vec<u8>,
+}
+
+/// Writes an archive out as files are added so only a single block of data
+/// is ever held in memory. Files are started with `start_file` and their
+/// contents written through the `Write` impl, much like `zip::ZipWriter`.
+pub struct ArchiveWriter<W: Write + Seek> {
+    writer: W,
+    options: WriteOptions,
+    // Where the archive starts in `writer`, pointers are relative to it
+    base: u64,
+    position: u32,
+    entries: Vec<(u32, u32, u32)>,
+    filenames: Vec<String>,
+    current: Option<CurrentFile>,
+    timestamp: Option<SystemTime>,
+    tracker: Tracker,
+}
+
+impl<W: Write + Seek> ArchiveWriter<W> {
+    pub fn new(writer: W) -> io::Result<Self> {
+        Self::with_options(writer, WriteOptions::default())
+    }
+
+    pub fn with_options(mut writer: W, options: WriteOptions) -> io::Result<Self> {
+        validate(&options)?;
+        let base = writer.stream_position()?;
+        // The entry table pointer isn't known until the end
+        write_header(&mut writer, 0)?;
+        Ok(Self {
+            writer,
+            options,
+            base,
+            position: HEADER_SIZE,
+            entries: Vec::new(),
+            filenames: Vec::new(),
+            current: None,
+            timestamp: None,
+            tracker: Tracker::new(options.progress, None),
+        })
+    }
+
+    /// Stamped into the footer instead of the time the archive is finished
+    pub fn set_timestamp(&mut self, timestamp: SystemTime) -> &mut Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn start_file(&mut self, name: &str) -> io::Result<()> {
+        self.finish_file()?;
+        self.current = Some(CurrentFile {
+            name: name.to_string(),
+            pointer: self.position,
+            size: 0,
+            buffer: Vec::with_capacity(self.options.block_size),
+        });
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.finish_file()?;
+
+        // The directory is stored as the final entry, after all files
+        let directory = directory(self.filenames.iter().map(String::as_bytes));
+        let pointer = self.position;
+        for chunk in directory.chunks(self.options.block_size) {
+            self.write_block(chunk)?;
+        }
+        self.entries
+            .push((DIRECTORY_CRC, pointer, to_u32(directory.len())?));
+
+        let entries = std::mem::take(&mut self.entries);
+        let footer = self.timestamp.unwrap_or_else(SystemTime::now);
+        let footer = Some(footer).filter(|_| self.options.footer);
+        write_index(&mut self.writer, entries, footer)?;
+
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(self.base))?;
+        write_header(&mut self.writer, self.position)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn finish_file(&mut self) -> io::Result<()> {
+        if let Some(file) = self.current.take() {
+            if !file.buffer.is_empty() {
+                self.write_block(&file.buffer)?;
+            }
+            self.entries
+                .push((filename_crc(&file.name), file.pointer, file.size));
+            self.filenames.push(file.name);
+            self.tracker.advance(file.size.into());
+        }
+        Ok(())
+    }
+
+    fn write_block(&mut self, chunk: &[u8]) -> io::Result<()> {
+        let block = block(chunk, self.options.compression)?;
+        self.writer.write_all(&block)?;
+        self.position = to_u32(self.position as usize + block.len())?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Write for ArchiveWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let block_size = self.options.block_size;
+        let file = self.current.as_mut().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "start_file must be called before writing",
+            )
+        })?;
+
+        let written = buf.len().min(block_size - file.buffer.len());
+        file.buffer.extend_from_slice(&buf[..written]);
+        file.size = to_u32(file.size as usize + written)?;
+
+        if file.buffer.len() == block_size {
+            let chunk = std::mem::replace(&mut file.buffer, Vec::with_capacity(block_size));
+            self.write_block(&chunk)?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
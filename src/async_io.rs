@@ -0,0 +1,33 @@
+use std::io;
+use std::path::Path;
+
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{parser, Archive, Error};
+
+pub async fn read_async(path: impl AsRef<Path>) -> Result<Archive<'static>, Error> {
+    let buffer = tokio::fs::read(path).await?;
+    parser::parse(buffer.into())
+}
+
+impl<'a> Archive<'a> {
+    /// Each file is only decompressed once the stream is polled for it, on
+    /// tokio's blocking thread pool so inflating doesn't hold up the runtime.
+    /// The file's compressed blocks are copied out for the task to own.
+    pub fn files_stream(&self) -> impl Stream<Item = Result<(String, Vec<u8>), Error>> + '_ {
+        stream::iter(self.filenames.iter().zip(self.entries.iter())).then(move |(name, entry)| {
+            let base = entry.data_span().start as usize;
+            let data = self.block_bytes(entry).to_vec();
+            let entry = entry.clone();
+            let inflater = self.inflater.clone();
+            async move {
+                let contents = tokio::task::spawn_blocking(move || {
+                    entry.decompress_from(&data, base, &*inflater)
+                })
+                .await
+                .map_err(io::Error::other)??;
+                Ok((name.clone(), contents))
+            }
+        })
+    }
+}
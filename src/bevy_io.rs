@@ -0,0 +1,97 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use bevy::asset::io::{AssetReader, AssetReaderError, PathStream, Reader, VecReader};
+use bevy::asset::{Asset, AssetLoader, LoadContext};
+use bevy::reflect::TypePath;
+use bevy::tasks::futures_lite::stream;
+
+use crate::{Archive, Error};
+
+/// Serves the files in an archive as a bevy asset source, registered with
+/// `AssetSourceBuilder::default().with_reader`. Archives are flat so the
+/// root is the only directory.
+#[derive(Clone)]
+pub struct ArchiveAssetReader {
+    archive: Arc<Archive<'static>>,
+}
+
+impl ArchiveAssetReader {
+    pub fn new(archive: impl Into<Arc<Archive<'static>>>) -> Self {
+        Self {
+            archive: archive.into(),
+        }
+    }
+
+    fn file(&self, path: &Path) -> Result<Vec<u8>, AssetReaderError> {
+        let not_found = || AssetReaderError::NotFound(path.to_path_buf());
+        let filename = path.to_str().ok_or_else(not_found)?;
+        match self.archive.get(filename) {
+            Ok(Some(data)) => Ok(data),
+            Ok(None) => Err(not_found()),
+            Err(Error::IO(error)) => Err(AssetReaderError::Io(Arc::new(error))),
+            Err(error) => Err(AssetReaderError::Io(Arc::new(std::io::Error::other(error)))),
+        }
+    }
+}
+
+impl AssetReader for ArchiveAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        self.file(path).map(VecReader::new)
+    }
+
+    // Archives don't store .meta files, bevy falls back to the loader's
+    // default settings
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<impl Reader + 'a, AssetReaderError> {
+        Err::<VecReader, _>(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        if !is_root(path) {
+            return Err(AssetReaderError::NotFound(path.to_path_buf()));
+        }
+        let paths: Vec<PathBuf> = self.archive.filenames().iter().map(PathBuf::from).collect();
+        Ok(Box::new(stream::iter(paths)))
+    }
+
+    async fn is_directory<'a>(&'a self, path: &'a Path) -> Result<bool, AssetReaderError> {
+        Ok(is_root(path))
+    }
+}
+
+fn is_root(path: &Path) -> bool {
+    path.as_os_str().is_empty() || path == Path::new("/")
+}
+
+/// A whole archive loaded as an asset, its files are read with the
+/// archive's own methods.
+#[derive(Asset, TypePath)]
+pub struct ArchiveAsset(pub Archive<'static>);
+
+/// Loads `.s3d`, `.eqg` and `.pfs` files as an `ArchiveAsset`.
+#[derive(Default)]
+pub struct ArchiveLoader;
+
+impl AssetLoader for ArchiveLoader {
+    type Asset = ArchiveAsset;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<ArchiveAsset, Error> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer).await?;
+        Archive::from_bytes(buffer).map(ArchiveAsset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["s3d", "eqg", "pfs"]
+    }
+}
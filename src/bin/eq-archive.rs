@@ -0,0 +1,135 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use eq_archive::{ArchiveBuilder, Error, ParseOptions, Severity};
+
+const USAGE: &str = "Usage:
+    eq-archive list <archive>
+    eq-archive extract <archive> [--out <dir>]
+    eq-archive cat <archive> <file>
+    eq-archive pack <archive> <file>...
+    eq-archive check <archive>...
+    eq-archive analyze <archive>";
+
+fn main() {
+    env_logger::init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["list", archive] => list(archive),
+        ["extract", archive] => extract(archive, Path::new(".")),
+        ["extract", archive, "--out", dir] => extract(archive, Path::new(dir)),
+        ["cat", archive, file] => cat(archive, file),
+        ["pack", archive, files @ ..] if !files.is_empty() => pack(archive, files),
+        ["check", archives @ ..] if !archives.is_empty() => check(archives),
+        ["analyze", archive] => analyze(archive),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("eq-archive: {}", e);
+        process::exit(1);
+    }
+}
+
+fn list(archive: &str) -> Result<(), Error> {
+    let archive = eq_archive::read(archive)?;
+    for filename in archive.filenames() {
+        println!("{}", filename);
+    }
+    Ok(())
+}
+
+fn extract(archive: &str, out: &Path) -> Result<(), Error> {
+    eq_archive::read(archive)?.extract_all(out)
+}
+
+fn cat(archive: &str, filename: &str) -> Result<(), Error> {
+    let archive = eq_archive::read(archive)?;
+    let data = archive
+        .get(filename)?
+        .ok_or_else(|| Error::FileNotFound(filename.to_string()))?;
+    io::stdout().write_all(&data)?;
+    Ok(())
+}
+
+fn pack(archive: &str, files: &[&str]) -> Result<(), Error> {
+    let mut builder = ArchiveBuilder::new();
+    for file in files {
+        let path = PathBuf::from(file);
+        let filename = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| Error::FileNotFound(file.to_string()))?;
+        builder.add_file(filename, fs::read(&path)?);
+    }
+    builder.write_to(archive)?;
+    Ok(())
+}
+
+// Exits with 1 if any archive has errors, warnings alone still pass
+fn check(archives: &[&str]) -> Result<(), Error> {
+    let options = ParseOptions {
+        recover: true,
+        ..Default::default()
+    };
+    let mut ok = true;
+    for path in archives {
+        let archive = eq_archive::read_with(path, &options)?;
+        let report = archive.check();
+        for issue in &report.issues {
+            let severity = match issue.severity {
+                Severity::Warning => "warning",
+                Severity::Error => "error",
+            };
+            println!("{}: {}: {}", path, severity, issue.error);
+        }
+        for failure in archive.failures() {
+            println!("{}: error: {}: {}", path, failure.filename, failure.error);
+        }
+        ok &= report.is_ok() && archive.failures().is_empty();
+    }
+    if !ok {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn analyze(archive: &str) -> Result<(), Error> {
+    let analysis = eq_archive::read(archive)?.analyze(10)?;
+
+    println!(
+        "{:<10} {:>6} {:>12} {:>12}",
+        "extension", "files", "compressed", "size"
+    );
+    for (extension, stats) in &analysis.by_extension {
+        println!(
+            "{:<10} {:>6} {:>12} {:>12}",
+            extension, stats.count, stats.compressed_size, stats.uncompressed_size
+        );
+    }
+
+    println!("\nlargest:");
+    for (filename, size) in &analysis.largest {
+        println!("{:>12} {}", size, filename);
+    }
+
+    if !analysis.duplicates.is_empty() {
+        println!("\nduplicates, {} bytes:", analysis.duplicate_size());
+        for group in &analysis.duplicates {
+            println!("{:>12} {}", group.size, group.filenames.join(", "));
+        }
+    }
+    Ok(())
+}
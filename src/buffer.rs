@@ -0,0 +1,56 @@
+use std::ops::Deref;
+
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+
+/// The bytes backing an archive, entries are decompressed out of this on demand
+#[derive(Debug)]
+pub enum Buffer<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+    #[cfg(feature = "mmap")]
+    Mapped(Mmap),
+}
+
+impl<'a> Buffer<'a> {
+    pub fn into_owned(self) -> Buffer<'static> {
+        match self {
+            Self::Borrowed(data) => Buffer::Owned(data.to_vec()),
+            Self::Owned(data) => Buffer::Owned(data),
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => Buffer::Mapped(map),
+        }
+    }
+}
+
+impl<'a> Deref for Buffer<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Borrowed(data) => data,
+            Self::Owned(data) => data,
+            #[cfg(feature = "mmap")]
+            Self::Mapped(map) => map,
+        }
+    }
+}
+
+impl<'a> From<&'a [u8]> for Buffer<'a> {
+    fn from(data: &'a [u8]) -> Self {
+        Self::Borrowed(data)
+    }
+}
+
+impl From<Vec<u8>> for Buffer<'static> {
+    fn from(data: Vec<u8>) -> Self {
+        Self::Owned(data)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl From<Mmap> for Buffer<'static> {
+    fn from(map: Mmap) -> Self {
+        Self::Mapped(map)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::crc::filename_crc;
+use crate::parser::{self, archive_len, check_format, check_overlaps, unnamed, DIRECTORY_CRC};
+use crate::{Archive, Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The archive can be read but isn't laid out the way the client writes them
+    Warning,
+    /// The client will fail to load the archive or some of its files
+    Error,
+}
+
+#[derive(Debug)]
+pub struct Issue {
+    pub severity: Severity,
+    pub error: Error,
+}
+
+#[derive(Debug, Default)]
+pub struct IntegrityReport {
+    pub issues: Vec<Issue>,
+}
+
+impl IntegrityReport {
+    /// True when there are at most warnings
+    pub fn is_ok(&self) -> bool {
+        self.issues
+            .iter()
+            .all(|issue| issue.severity < Severity::Error)
+    }
+
+    fn push(&mut self, severity: Severity, error: Error) {
+        self.issues.push(Issue { severity, error });
+    }
+}
+
+impl Archive<'_> {
+    /// Runs every check `ParseOptions::strict` does without stopping at the
+    /// first problem. Every file is inflated. Entries that were skipped while
+    /// recovering are left to `failures`.
+    pub fn check(&self) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+
+        if let Err(e) = check_format(&self.data) {
+            report.push(Severity::Error, e);
+        }
+        if let Ok(len) = archive_len(&self.data) {
+            if len < self.data.len() {
+                report.push(Severity::Warning, Error::TrailingData { offset: len });
+            }
+        }
+        if let Err(e) = check_overlaps(&self.header, self.entries.iter().chain(&self.directory)) {
+            report.push(Severity::Error, e);
+        }
+
+        match &self.directory {
+            Some(directory) => {
+                let names = self
+                    .decompress(directory)
+                    .and_then(|names| Ok(parser::read_directory(&names)?.len()));
+                // Patchers can leave stale directories behind, none of them are files
+                let directories = parser::entry_table(&self.data[self.header.pointer as usize..])
+                    .map(|(_, (_, entries, _))| {
+                        entries
+                            .iter()
+                            .filter(|entry| entry.filename_crc == DIRECTORY_CRC)
+                            .count()
+                    })
+                    .unwrap_or(1);
+                let files = self.entry_count as usize - directories;
+                match names {
+                    Ok(names) if names != files => report.push(
+                        Severity::Error,
+                        Error::DirectoryMismatch {
+                            entries: files,
+                            names,
+                        },
+                    ),
+                    Ok(_) => {}
+                    Err(e) => report.push(Severity::Error, e),
+                }
+            }
+            None => report.push(Severity::Warning, Error::MissingDirectory),
+        }
+
+        for ((filename, raw_filename), entry) in self
+            .filenames
+            .iter()
+            .zip(&self.raw_filenames)
+            .zip(&self.entries)
+        {
+            let actual = filename_crc(raw_filename);
+            // Names made up for unlisted entries don't have a CRC to check
+            let listed = *raw_filename != unnamed(entry.filename_crc).into_bytes();
+            if listed && actual != entry.filename_crc {
+                report.push(
+                    Severity::Error,
+                    Error::CrcMismatch {
+                        filename: filename.clone(),
+                        expected: entry.filename_crc,
+                        actual,
+                    },
+                );
+            }
+            if let Err(e) = self.decompress(entry) {
+                report.push(Severity::Error, e);
+            }
+        }
+
+        report
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{Archive, Error};
+
+/// What a file is, going by its first few bytes rather than its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Wld,
+    Bmp,
+    Dds,
+    Wav,
+    /// Printable ASCII only, like the .txt and .lit files in zone archives
+    Txt,
+    Unknown,
+}
+
+// 0x54503D02 as a little endian integer
+const WLD_MAGIC: &[u8; 4] = b"\x02\x3DPT";
+// Looked at to decide whether a file is text, most are only a few lines
+const TEXT_SAMPLE: usize = 512;
+
+impl FileKind {
+    pub fn detect(data: &[u8]) -> Self {
+        if data.starts_with(WLD_MAGIC) {
+            Self::Wld
+        } else if data.starts_with(b"BM") {
+            Self::Bmp
+        } else if data.starts_with(b"DDS ") {
+            Self::Dds
+        } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WAVE") {
+            Self::Wav
+        } else if !data.is_empty() && data.iter().take(TEXT_SAMPLE).all(is_text) {
+            Self::Txt
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+fn is_text(byte: &u8) -> bool {
+    byte.is_ascii_graphic() || byte.is_ascii_whitespace()
+}
+
+impl Archive<'_> {
+    /// Like `iter_files`, with each file's kind so it can be handed to the
+    /// right decoder
+    pub fn classified_files(
+        &self,
+    ) -> impl Iterator<Item = Result<(String, FileKind, Vec<u8>), Error>> + '_ {
+        self.iter_files().map(|file| {
+            let (name, data) = file?;
+            Ok((name, FileKind::detect(&data), data))
+        })
+    }
+}
@@ -0,0 +1,36 @@
+//! The CRC used to key entries by filename, a CRC-32 with the standard
+//! polynomial but without reflection or a final XOR
+
+const CRC_POLYNOMIAL: u32 = 0x04C1_1DB7;
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u32) << 24;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ CRC_POLYNOMIAL
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Entries are keyed by a CRC of their null terminated filename. Names are
+/// hashed as the raw bytes stored in the directory, see `Archive::raw_filenames`.
+pub fn filename_crc(name: impl AsRef<[u8]>) -> u32 {
+    name.as_ref()
+        .iter()
+        .copied()
+        .chain(std::iter::once(0))
+        .fold(0, |crc, byte| {
+            (crc << 8) ^ CRC_TABLE[((crc >> 24) as u8 ^ byte) as usize]
+        })
+}
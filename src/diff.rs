@@ -0,0 +1,51 @@
+use crate::{Archive, Entry, Error};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ArchiveDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl ArchiveDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Lists the files that would need to change to turn `a` into `b`
+pub fn diff(a: &Archive, b: &Archive) -> Result<ArchiveDiff, Error> {
+    let mut diff = ArchiveDiff::default();
+
+    for (filename, entry) in a.filenames.iter().zip(a.entries.iter()) {
+        match b
+            .index_of(filename)
+            .and_then(|position| b.entries.get(position))
+        {
+            Some(other) => {
+                if !same_contents(a, entry, b, other)? {
+                    diff.changed.push(filename.clone());
+                }
+            }
+            None => diff.removed.push(filename.clone()),
+        }
+    }
+
+    diff.added = b
+        .filenames
+        .iter()
+        .filter(|filename| !a.contains(filename))
+        .cloned()
+        .collect();
+
+    Ok(diff)
+}
+
+fn same_contents(a: &Archive, entry: &Entry, b: &Archive, other: &Entry) -> Result<bool, Error> {
+    if entry.uncompressed_size != other.uncompressed_size {
+        return Ok(false);
+    }
+    // Comparing checksums rather than contents avoids holding both files at once
+    let checksum = crc32fast::hash(&a.decompress(entry)?);
+    Ok(checksum == crc32fast::hash(&b.decompress(other)?))
+}
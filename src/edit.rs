@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{self, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::crc::filename_crc;
+use crate::parser::{DIRECTORY_CRC, HEADER_SIZE};
+use crate::writer::{directory, to_u32, write_blocks, write_header, write_index, Name};
+use crate::{Archive, ArchiveBuilder, Error, LookupOptions, WriteOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EditMode {
+    /// Every file is recompressed into a fresh, compact archive
+    #[default]
+    Rebuild,
+    /// Changed files and a new directory are appended after the existing block
+    /// data, which is kept byte for byte. Replaced and removed files are left
+    /// behind as unreferenced blocks.
+    Append,
+}
+
+// Files that haven't been touched still refer to their entry in the archive
+enum Contents {
+    Entry(usize),
+    Data(Vec<u8>),
+}
+
+type Files = Vec<(String, Contents)>;
+
+impl Archive<'_> {
+    pub fn set_edit_mode(&mut self, mode: EditMode) {
+        self.edit_mode = mode;
+    }
+
+    pub fn insert(&mut self, filename: &str, data: impl Into<Vec<u8>>) -> Result<(), Error> {
+        let data = data.into();
+        self.rebuild(|_, files| {
+            if find(files, filename, LookupOptions::default()).is_some() {
+                return Err(Error::FileExists(filename.to_string()));
+            }
+            files.push((filename.to_string(), Contents::Data(data)));
+            Ok(())
+        })
+    }
+
+    pub fn remove(&mut self, filename: &str) -> Result<Vec<u8>, Error> {
+        let mut removed = Vec::new();
+        self.rebuild(|archive, files| {
+            let position = find(files, filename, archive.lookup)
+                .ok_or_else(|| Error::FileNotFound(filename.to_string()))?;
+            removed = archive.contents(files.remove(position).1)?;
+            Ok(())
+        })?;
+        Ok(removed)
+    }
+
+    /// Only the directory and the file's CRC change, every file's blocks are
+    /// copied over as they are whatever the edit mode, so fixing a name never
+    /// recompresses anything. The directory keeps its order and the footer,
+    /// if there is one, its timestamp.
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), Error> {
+        let files: Vec<_> = self
+            .filenames
+            .iter()
+            .enumerate()
+            .map(|(position, name)| (name.clone(), Contents::Entry(position)))
+            .collect();
+        let position =
+            find(&files, old, self.lookup).ok_or_else(|| Error::FileNotFound(old.to_string()))?;
+        if matches!(find(&files, new, LookupOptions::default()), Some(existing) if existing != position)
+        {
+            return Err(Error::FileExists(new.to_string()));
+        }
+        let buffer = self.write_renamed(position, new)?;
+        self.reparse(buffer)
+    }
+
+    pub fn replace(&mut self, filename: &str, data: impl Into<Vec<u8>>) -> Result<Vec<u8>, Error> {
+        let mut replaced = Vec::new();
+        self.rebuild(|archive, files| {
+            let position = find(files, filename, archive.lookup)
+                .ok_or_else(|| Error::FileNotFound(filename.to_string()))?;
+            let old = std::mem::replace(&mut files[position].1, Contents::Data(data.into()));
+            replaced = archive.contents(old)?;
+            Ok(())
+        })?;
+        Ok(replaced)
+    }
+
+    /// Writes the archive back out with names listed in the same order as
+    /// the data and every CRC matching its name, for archives from packers
+    /// that got either wrong
+    pub fn repair(&mut self) -> Result<(), Error> {
+        self.rebuild(|_, _| Ok(()))
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        Ok(self.write_identical(path)?)
+    }
+
+    /// The archive byte for byte as it was read, including the entry order,
+    /// any padding between blocks and the footer. Once it has been edited
+    /// these are the bytes of the rebuilt archive instead.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn write_identical(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        File::create(path)?.write_all(&self.data)
+    }
+
+    // Writes out a new archive with the change applied so that the directory,
+    // CRCs and pointers stay consistent, then parses it back in place of this one
+    fn rebuild<F>(&mut self, change: F) -> Result<(), Error>
+    where
+        F: FnOnce(&Self, &mut Files) -> Result<(), Error>,
+    {
+        let mut files = self
+            .filenames
+            .iter()
+            .enumerate()
+            .map(|(position, name)| (name.clone(), Contents::Entry(position)))
+            .collect();
+        change(self, &mut files)?;
+
+        let buffer = match self.edit_mode {
+            EditMode::Rebuild => self.write_rebuilt(files)?,
+            EditMode::Append => self.write_appended(files)?,
+        };
+        self.reparse(buffer)
+    }
+
+    fn write_rebuilt(&self, files: Files) -> Result<Vec<u8>, Error> {
+        // Archives without a footer, like most .eqg files, are kept that way
+        let mut builder = ArchiveBuilder::with_options(WriteOptions {
+            footer: self.footer.is_some(),
+            ..Default::default()
+        });
+        for (name, contents) in files {
+            let name = self.entry_name(&name, &contents);
+            builder.add_entry(name, self.contents(contents)?);
+        }
+        let mut buffer = Vec::new();
+        builder.write(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    fn write_appended(&self, files: Files) -> Result<Vec<u8>, Error> {
+        let options = WriteOptions::default();
+        // Everything before the entry table is kept as it is
+        let mut buffer = self.data[..self.header.pointer as usize].to_vec();
+
+        let mut entries = Vec::with_capacity(files.len());
+        for (name, contents) in files {
+            let name = self.entry_name(&name, &contents);
+            let (pointer, size) = match contents {
+                Contents::Entry(position) => {
+                    let entry = &self.entries[position];
+                    (entry.pointer, entry.uncompressed_size)
+                }
+                Contents::Data(data) => {
+                    let pointer = to_u32(buffer.len())?;
+                    write_blocks(&mut buffer, &data, &options)?;
+                    (pointer, to_u32(data.len())?)
+                }
+            };
+            entries.push((name, pointer, size));
+        }
+        // Names in the directory are in the same order as the data
+        entries.sort_by_key(|&(_, pointer, _)| pointer);
+
+        let listed: Vec<_> = entries
+            .iter()
+            .filter_map(|(name, _, _)| name.listed())
+            .collect();
+        let directory = directory(listed.into_iter());
+        let directory_pointer = to_u32(buffer.len())?;
+        write_blocks(&mut buffer, &directory, &options)?;
+
+        let mut index: Vec<_> = entries
+            .iter()
+            .map(|(name, pointer, size)| (name.crc(), *pointer, *size))
+            .collect();
+        index.push((DIRECTORY_CRC, directory_pointer, to_u32(directory.len())?));
+
+        let index_pointer = to_u32(buffer.len())?;
+        buffer[..4].copy_from_slice(&index_pointer.to_le_bytes());
+        let footer = self.footer.as_ref().map(|_| SystemTime::now());
+        write_index(&mut buffer, index, footer)?;
+        Ok(buffer)
+    }
+
+    fn write_renamed(&self, renamed: usize, name: &str) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        let mut index = Vec::with_capacity(self.entries.len() + 1);
+        // Files that share blocks keep sharing them
+        let mut written: HashMap<u32, u32> = HashMap::new();
+        for (position, entry) in self.entries.iter().enumerate() {
+            let filename_crc = if position == renamed {
+                filename_crc(name.as_bytes())
+            } else {
+                entry.filename_crc
+            };
+            let pointer = match written.get(&entry.pointer) {
+                Some(&pointer) => pointer,
+                None => {
+                    let pointer = to_u32(HEADER_SIZE as usize + data.len())?;
+                    data.extend_from_slice(self.block_bytes(entry));
+                    if !entry.blocks.is_empty() {
+                        written.insert(entry.pointer, pointer);
+                    }
+                    pointer
+                }
+            };
+            index.push((filename_crc, pointer, entry.uncompressed_size));
+        }
+
+        // Unnamed entries stay out of the directory unless they're being named
+        let names: Vec<_> = self
+            .directory_order()?
+            .into_iter()
+            .filter_map(|position| {
+                if position == renamed {
+                    Some(name.as_bytes().to_vec())
+                } else if self.is_unnamed(position) {
+                    None
+                } else {
+                    Some(self.raw_filenames[position].clone())
+                }
+            })
+            .collect();
+        let directory = directory(names.iter().map(Vec::as_slice));
+        let directory_pointer = to_u32(HEADER_SIZE as usize + data.len())?;
+        write_blocks(&mut data, &directory, &WriteOptions::default())?;
+        index.push((DIRECTORY_CRC, directory_pointer, to_u32(directory.len())?));
+
+        let mut buffer = Vec::with_capacity(self.data.len());
+        write_header(&mut buffer, to_u32(HEADER_SIZE as usize + data.len())?)?;
+        buffer.extend_from_slice(&data);
+        write_index(&mut buffer, index, self.timestamp())?;
+        Ok(buffer)
+    }
+
+    // Untouched names are written back as they were stored
+    fn entry_name(&self, name: &str, contents: &Contents) -> Name {
+        match contents {
+            Contents::Entry(position) if self.filenames[*position] == name => {
+                self.stored_name(*position)
+            }
+            _ => Name::Listed(name.as_bytes().to_vec()),
+        }
+    }
+
+    // The original bytes of the entry's name, or its original CRC when the
+    // directory didn't list it
+    pub(crate) fn stored_name(&self, position: usize) -> Name {
+        if self.is_unnamed(position) {
+            Name::Unlisted(self.entries[position].filename_crc)
+        } else {
+            Name::Listed(self.raw_filenames[position].clone())
+        }
+    }
+
+    fn contents(&self, contents: Contents) -> Result<Vec<u8>, Error> {
+        match contents {
+            Contents::Entry(position) => self.decompress(&self.entries[position]),
+            Contents::Data(data) => Ok(data),
+        }
+    }
+}
+
+// Names that only differ in case are always treated as the same file when
+// adding or renaming one, since that's how the client will see them
+fn find(files: &[(String, Contents)], filename: &str, lookup: LookupOptions) -> Option<usize> {
+    files.iter().position(|(name, _)| {
+        if lookup.case_sensitive {
+            name == filename
+        } else {
+            name.eq_ignore_ascii_case(filename)
+        }
+    })
+}
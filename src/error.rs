@@ -0,0 +1,268 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use nom::error::ErrorKind;
+
+use crate::{Format, SUPPORTED_VERSIONS};
+
+// Bytes of the input kept either side of where parsing failed
+const CONTEXT_SIZE: usize = 16;
+
+/// What was being read when parsing failed. Blocks have their own errors with
+/// offsets, and the footer is optional so a broken one is just not read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Header,
+    EntryTable,
+    Directory,
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Header => write!(f, "header"),
+            Self::EntryTable => write!(f, "entry table"),
+            Self::Directory => write!(f, "directory"),
+        }
+    }
+}
+
+/// Offsets are from the start of the archive unless noted otherwise
+#[derive(Debug)]
+pub enum Error {
+    IO(io::Error),
+    /// The nom combinator that failed and where. The offset is into the
+    /// inflated data for the directory. `before` and `after` are up to 16
+    /// bytes of the input either side of it, to show in bug reports.
+    Parser {
+        section: Section,
+        offset: usize,
+        kind: ErrorKind,
+        before: Vec<u8>,
+        after: Vec<u8>,
+    },
+    /// Offset of the compressed data of the block that failed to inflate
+    Decompress {
+        offset: usize,
+        source: io::Error,
+    },
+    /// Offset of the compressed data of the block whose zlib checksum doesn't
+    /// match what it inflated to
+    ChecksumMismatch {
+        offset: usize,
+        expected: u32,
+        actual: u32,
+    },
+    MissingDirectory,
+    NotAPfsArchive {
+        found_magic: u32,
+    },
+    UnsupportedFormat(Format),
+    UnsupportedVersion {
+        version: u32,
+    },
+    /// Offset where the block header was expected
+    TruncatedBlock {
+        offset: usize,
+    },
+    CorruptBlock {
+        offset: usize,
+    },
+    /// Offset of the block, or of the entry when the whole entry is the wrong size
+    SizeMismatch {
+        offset: usize,
+        expected: u32,
+        actual: usize,
+    },
+    /// Only reported when parsing strictly
+    TrailingData {
+        offset: usize,
+    },
+    DirectoryMismatch {
+        entries: usize,
+        names: usize,
+    },
+    /// Offset into the inflated directory where reading names stopped, after
+    /// the first `names` of them were read
+    CorruptDirectory {
+        offset: usize,
+        names: usize,
+    },
+    CrcMismatch {
+        filename: String,
+        expected: u32,
+        actual: u32,
+    },
+    /// Offset of the entry whose data starts inside another's
+    OverlappingBlocks {
+        offset: usize,
+    },
+    /// Named after the field of `Limits` that was exceeded
+    LimitExceeded {
+        limit: &'static str,
+        max: u64,
+    },
+    FileNotFound(String),
+    FileExists(String),
+    UnsafePath(String),
+    /// From `names::validate`, the reason finishes "can't be written because"
+    InvalidFilename {
+        filename: String,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IO(e) => write!(f, "i/o error: {}", e),
+            Self::Parser {
+                section,
+                offset,
+                kind,
+                before,
+                after,
+            } => write!(
+                f,
+                "malformed {} at offset {:#x}: {}, around {:02x?} | {:02x?}",
+                section,
+                offset,
+                kind.description(),
+                before,
+                after
+            ),
+            Self::Decompress { offset, source } => {
+                write!(
+                    f,
+                    "block at offset {:#x} failed to inflate: {}",
+                    offset, source
+                )
+            }
+            Self::ChecksumMismatch {
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "block at offset {:#x} inflated with Adler-32 {:#010x} but stores {:#010x}",
+                offset, actual, expected
+            ),
+            Self::MissingDirectory => write!(f, "archive has no directory"),
+            Self::NotAPfsArchive { found_magic } => write!(
+                f,
+                "not a PFS archive, found magic {:#010x} instead of \"PFS \"",
+                found_magic
+            ),
+            Self::UnsupportedFormat(format) => {
+                write!(f, "{:?} archives can't be read yet", format)
+            }
+            Self::UnsupportedVersion { version } => write!(
+                f,
+                "unsupported PFS version {:#010x}, expected one of {:#010x?}",
+                version, SUPPORTED_VERSIONS
+            ),
+            Self::TruncatedBlock { offset } => {
+                write!(
+                    f,
+                    "block at offset {:#x} runs past the end of the archive",
+                    offset
+                )
+            }
+            Self::CorruptBlock { offset } => {
+                write!(f, "block at offset {:#x} has an invalid size", offset)
+            }
+            Self::SizeMismatch {
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "data at offset {:#x} inflated to {} bytes but its stored size is {}",
+                offset, actual, expected
+            ),
+            Self::TrailingData { offset } => {
+                write!(
+                    f,
+                    "unexpected data at offset {:#x} after the entry table",
+                    offset
+                )
+            }
+            Self::DirectoryMismatch { entries, names } => {
+                write!(f, "directory lists {} names for {} entries", names, entries)
+            }
+            Self::CorruptDirectory { offset, names } => write!(
+                f,
+                "directory is corrupt at offset {:#x} of its data, after {} names",
+                offset, names
+            ),
+            Self::CrcMismatch {
+                filename,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} has CRC {:#010x} but its entry has {:#010x}",
+                filename, actual, expected
+            ),
+            Self::OverlappingBlocks { offset } => {
+                write!(f, "data at offset {:#x} overlaps another entry", offset)
+            }
+            Self::LimitExceeded { limit, max } => {
+                write!(f, "archive exceeds {} of {}", limit, max)
+            }
+            Self::FileNotFound(filename) => write!(f, "{} not found in archive", filename),
+            Self::FileExists(filename) => write!(f, "{} already exists in archive", filename),
+            Self::UnsafePath(filename) => {
+                write!(
+                    f,
+                    "{} would be extracted outside of the destination",
+                    filename
+                )
+            }
+            Self::InvalidFilename { filename, reason } => {
+                write!(f, "{:?} can't be written because {}", filename, reason)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::IO(e) | Self::Decompress { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::IO(e)
+    }
+}
+
+impl Error {
+    // `input` is what the failed parser was given, which starts at `start`
+    pub(crate) fn parser(
+        section: Section,
+        input: &[u8],
+        start: usize,
+        e: nom::Err<(&[u8], ErrorKind)>,
+    ) -> Self {
+        let (position, kind) = match e {
+            nom::Err::Error((remaining, kind)) | nom::Err::Failure((remaining, kind)) => {
+                (input.len().saturating_sub(remaining.len()), kind)
+            }
+            // Only streaming parsers ask for more input, running out is the closest match
+            nom::Err::Incomplete(_) => (input.len(), ErrorKind::Eof),
+        };
+        Self::Parser {
+            section,
+            offset: start + position,
+            kind,
+            before: input[position.saturating_sub(CONTEXT_SIZE)..position].to_vec(),
+            after: input[position..(position + CONTEXT_SIZE).min(input.len())].to_vec(),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::fs::{self, File};
+#[cfg(feature = "hash")]
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::progress::Tracker;
+use crate::{Archive, Error};
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Set the modified time of extracted files to the archive footer timestamp
+    pub preserve_timestamp: bool,
+    /// Keep decompressed files on disk so extracting the same archive again,
+    /// even from another run, copies them instead of inflating them
+    #[cfg(feature = "hash")]
+    pub cache: Option<CacheConfig>,
+}
+
+/// Files are cached under a directory named after the SHA-256 of the
+/// archive's bytes, so a changed archive never reads stale files. Once the
+/// cache grows past `max_bytes` the archives cached longest ago are dropped
+/// first.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+}
+
+#[cfg(feature = "hash")]
+impl CacheConfig {
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+        }
+    }
+
+    // Removes cached archives, oldest first, until the cache fits. The one
+    // named `keep` goes last, and only if it's too big on its own.
+    fn trim(&self, keep: &Path) -> io::Result<()> {
+        let mut cached = Vec::new();
+        let mut total = 0;
+        for dir in fs::read_dir(&self.dir)? {
+            let dir = dir?;
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+            let path = dir.path();
+            let size = dir_size(&path)?;
+            let modified = dir.metadata()?.modified()?;
+            total += size;
+            cached.push((path == keep, modified, path, size));
+        }
+        cached.sort();
+
+        for (_, _, path, size) in cached {
+            if total <= self.max_bytes {
+                break;
+            }
+            fs::remove_dir_all(path)?;
+            total -= size;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Archive<'a> {
+    pub fn extract_all(&self, dest: &Path) -> Result<(), Error> {
+        self.extract_all_with(dest, &ExtractOptions::default())
+    }
+
+    pub fn extract_all_with(&self, dest: &Path, options: &ExtractOptions) -> Result<(), Error> {
+        let modified = self.timestamp().filter(|_| options.preserve_timestamp);
+        let mut tracker = Tracker::new(self.progress, Some(self.entries.len()));
+        #[cfg(feature = "hash")]
+        let cache = options
+            .cache
+            .as_ref()
+            .map(|config| (config, config.dir.join(self.hash().to_string())));
+
+        for (filename, entry) in self.filenames.iter().zip(self.entries.iter()) {
+            let relative = sanitize(filename)?;
+            let path = dest.join(&relative);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            #[cfg(feature = "hash")]
+            let cached = cache.as_ref().map(|(_, dir)| dir.join(&relative));
+            #[cfg(not(feature = "hash"))]
+            let cached: Option<PathBuf> = None;
+            match cached {
+                Some(cached) => {
+                    // A short file is left over from an interrupted run
+                    let hit = fs::metadata(&cached)
+                        .map(|metadata| metadata.len() == u64::from(entry.size()))
+                        .unwrap_or(false);
+                    if !hit {
+                        if let Some(parent) = cached.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        fs::write(&cached, self.decompress(entry)?)?;
+                    }
+                    fs::copy(&cached, &path)?;
+                }
+                None => fs::write(&path, self.decompress(entry)?)?,
+            }
+            if let Some(modified) = modified {
+                File::options()
+                    .write(true)
+                    .open(&path)?
+                    .set_modified(modified)?;
+            }
+            tracker.advance(u64::from(entry.size()));
+        }
+        #[cfg(feature = "hash")]
+        if let Some((config, dir)) = &cache {
+            config.trim(dir)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hash")]
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+// Stored names may use either separator, anything that would escape the
+// destination directory is rejected rather than written
+fn sanitize(filename: &str) -> Result<PathBuf, Error> {
+    let normalized = filename.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            // Drive letters are only meaningful to Windows but never belong in a name
+            Component::Normal(part) if !part.to_string_lossy().contains(':') => path.push(part),
+            Component::CurDir => {}
+            _ => return Err(Error::UnsafePath(filename.to_string())),
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return Err(Error::UnsafePath(filename.to_string()));
+    }
+    Ok(path)
+}
@@ -0,0 +1,126 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::ptr;
+
+use crate::Archive;
+
+/// Opaque handle given out to C, the filename pointers stay valid until it's freed
+pub struct EqArchive {
+    archive: Archive<'static>,
+    _filenames: Vec<CString>,
+    pointers: Vec<*const c_char>,
+}
+
+/// # Safety
+/// `path` must be a valid null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn eq_archive_open(path: *const c_char) -> *mut EqArchive {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+    let archive = match CStr::from_ptr(path)
+        .to_str()
+        .ok()
+        .and_then(|path| crate::read(path).ok())
+    {
+        Some(archive) => archive,
+        None => return ptr::null_mut(),
+    };
+
+    let filenames: Vec<CString> = archive
+        .filenames()
+        .iter()
+        .map(|filename| {
+            // Anything after an embedded null would be invisible to C anyway
+            let bytes = filename.split('\0').next().unwrap_or_default();
+            CString::new(bytes).unwrap_or_default()
+        })
+        .collect();
+    let pointers = filenames.iter().map(|filename| filename.as_ptr()).collect();
+
+    Box::into_raw(Box::new(EqArchive {
+        archive,
+        _filenames: filenames,
+        pointers,
+    }))
+}
+
+/// # Safety
+/// `archive` must have come from `eq_archive_open` and `count` must be valid to write to.
+#[no_mangle]
+pub unsafe extern "C" fn eq_archive_filenames(
+    archive: *const EqArchive,
+    count: *mut usize,
+) -> *const *const c_char {
+    if archive.is_null() || count.is_null() {
+        return ptr::null();
+    }
+    let archive = &*archive;
+    *count = archive.pointers.len();
+    archive.pointers.as_ptr()
+}
+
+/// # Safety
+/// `archive` must have come from `eq_archive_open`, `filename` must be a valid
+/// null terminated string and `len` must be valid to write to. The returned
+/// data must be released with `eq_archive_free_data`.
+#[no_mangle]
+pub unsafe extern "C" fn eq_archive_get(
+    archive: *const EqArchive,
+    filename: *const c_char,
+    len: *mut usize,
+) -> *mut u8 {
+    if archive.is_null() || filename.is_null() || len.is_null() {
+        return ptr::null_mut();
+    }
+    let data = match CStr::from_ptr(filename)
+        .to_str()
+        .ok()
+        .and_then(|filename| (*archive).archive.get(filename).ok().flatten())
+    {
+        Some(data) => data.into_boxed_slice(),
+        None => return ptr::null_mut(),
+    };
+    *len = data.len();
+    Box::into_raw(data) as *mut u8
+}
+
+/// # Safety
+/// `archive` must have come from `eq_archive_open` and `dest` must be a valid
+/// null terminated string. Returns 0 on success and -1 on failure.
+#[no_mangle]
+pub unsafe extern "C" fn eq_archive_extract_all(
+    archive: *const EqArchive,
+    dest: *const c_char,
+) -> c_int {
+    if archive.is_null() || dest.is_null() {
+        return -1;
+    }
+    let extracted = CStr::from_ptr(dest)
+        .to_str()
+        .ok()
+        .and_then(|dest| (*archive).archive.extract_all(Path::new(dest)).ok());
+    match extracted {
+        Some(()) => 0,
+        None => -1,
+    }
+}
+
+/// # Safety
+/// `data` and `len` must be exactly as returned by `eq_archive_get`.
+#[no_mangle]
+pub unsafe extern "C" fn eq_archive_free_data(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// # Safety
+/// `archive` must have come from `eq_archive_open` and not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn eq_archive_free(archive: *mut EqArchive) {
+    if !archive.is_null() {
+        drop(Box::from_raw(archive));
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(feature = "hash")]
+use std::fmt;
+#[cfg(all(feature = "hash", feature = "fs"))]
+use std::fs::File;
+#[cfg(all(feature = "hash", feature = "fs"))]
+use std::io::Read;
+#[cfg(all(feature = "hash", feature = "fs"))]
+use std::path::Path;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "hash")]
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "hash")]
+use crate::parser::{footer, FOOTER_SIZE};
+use crate::Archive;
+#[cfg(all(feature = "hash", feature = "fs"))]
+use crate::Error;
+
+/// Identifies an archive's exact bytes, for launchers checking an install
+/// against a patch manifest. Displays as the hex SHA-256.
+#[cfg(feature = "hash")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ArchiveHash {
+    pub sha256: [u8; 32],
+    /// Seconds since the Unix epoch from the footer, if it has one
+    pub timestamp: Option<u32>,
+}
+
+#[cfg(feature = "hash")]
+impl ArchiveHash {
+    fn new(sha256: [u8; 32], tail: &[u8]) -> Self {
+        let timestamp = tail
+            .len()
+            .checked_sub(FOOTER_SIZE)
+            .and_then(|start| footer(&tail[start..]).ok())
+            .map(|(_, footer)| footer.timestamp());
+        Self { sha256, timestamp }
+    }
+}
+
+#[cfg(feature = "hash")]
+impl fmt::Display for ArchiveHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.sha256 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Hashes the file as it is on disk without parsing any of it, reading it
+/// front to back in chunks. The footer is taken from the last bytes, so
+/// anything that isn't an archive just has no timestamp.
+#[cfg(all(feature = "hash", feature = "fs"))]
+pub fn hash_file(path: impl AsRef<Path>) -> Result<ArchiveHash, Error> {
+    let mut file = File::open(path)?;
+    let mut sha256 = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    let mut tail = Vec::with_capacity(FOOTER_SIZE * 2);
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        sha256.update(&buf[..read]);
+        // Reads can be shorter than the footer, so keep the last few bytes
+        // across them
+        tail.extend_from_slice(&buf[read.saturating_sub(FOOTER_SIZE)..read]);
+        tail.drain(..tail.len().saturating_sub(FOOTER_SIZE));
+    }
+    Ok(ArchiveHash::new(sha256.finalize().into(), &tail))
+}
+
+/// A cheap stand in for `ArchiveHash` for deciding whether an archive needs
+/// extracting again. Nothing is inflated, the CRC covers the entry table,
+/// footer and the directory's blocks, so renamed, added or resized files all
+/// change it. A file rewritten with the same size, names and timestamp
+/// doesn't, compare `ArchiveHash`es when that matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fingerprint {
+    pub timestamp: Option<u32>,
+    /// Files in the archive, not counting the directory
+    pub entry_count: u32,
+    pub crc32: u32,
+}
+
+impl Archive<'_> {
+    pub fn fingerprint(&self) -> Fingerprint {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&self.data[self.header.pointer as usize..]);
+        if let Some(directory) = &self.directory {
+            hasher.update(self.block_bytes(directory));
+        }
+        Fingerprint {
+            timestamp: self.footer.as_ref().map(|footer| footer.timestamp),
+            entry_count: self.entries.len() as u32,
+            crc32: hasher.finalize(),
+        }
+    }
+
+    /// The same as `hash_file` gives for the file the archive was read from,
+    /// until it's edited
+    #[cfg(feature = "hash")]
+    pub fn hash(&self) -> ArchiveHash {
+        let data = self.as_bytes();
+        ArchiveHash::new(Sha256::digest(data).into(), data)
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt::Debug;
+use std::io::{self, Read};
+
+use flate2::read::ZlibDecoder;
+
+/// Decompresses the zlib stream stored in a single block. The flate2 backend
+/// used by default is picked with cargo features, anything else (a hardware
+/// inflater for instance) can be plugged in with `ParseOptions::inflater`.
+/// `size` is the uncompressed size stored for the block. Anything inflating
+/// past it is rejected, so a block claiming to be small can't be used to
+/// fill memory: stop reading a byte after it.
+pub trait Inflate: Debug + Send + Sync {
+    fn inflate(&self, block: &[u8], size: usize, out: &mut Vec<u8>) -> io::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zlib;
+
+impl Inflate for Zlib {
+    fn inflate(&self, block: &[u8], size: usize, out: &mut Vec<u8>) -> io::Result<()> {
+        ZlibDecoder::new(block)
+            .take(size as u64 + 1)
+            .read_to_end(out)
+            .map(|_| ())
+    }
+}
+
+// The checksum zlib stores big endian after the deflated data, of the data
+// before it was deflated
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 5552 bytes is the most that can be summed before `b` could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::Arc;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::Section;
+use crate::inflate::{Inflate, Zlib};
+use crate::parser::{
+    self, associate, blocks, check_format, directory_positions, entry_table, index, unnamed, Entry,
+    HEADER_SIZE,
+};
+use crate::{normalize_filename, Error, ParseOptions};
+
+/// Somewhere an archive can be read from in pieces, like a file or a server
+/// that supports HTTP range requests
+pub trait RandomAccess {
+    fn size(&self) -> io::Result<u64>;
+
+    /// Fills `buf` with the bytes starting at `offset`, failing if the source
+    /// ends before it's full
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+impl RandomAccess for [u8] {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let bytes = usize::try_from(offset)
+            .ok()
+            .and_then(|start| self.get(start..start.checked_add(buf.len())?))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl RandomAccess for Vec<u8> {
+    fn size(&self) -> io::Result<u64> {
+        self.as_slice().size()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.as_slice().read_at(offset, buf)
+    }
+}
+
+impl<T: RandomAccess + ?Sized> RandomAccess for &T {
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+#[cfg(all(feature = "fs", unix))]
+impl RandomAccess for File {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        std::os::unix::fs::FileExt::read_exact_at(self, buf, offset)
+    }
+}
+
+#[cfg(all(feature = "fs", windows))]
+impl RandomAccess for File {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&self, mut offset: u64, mut buf: &mut [u8]) -> io::Result<()> {
+        use std::os::windows::fs::FileExt;
+
+        while !buf.is_empty() {
+            match self.seek_read(buf, offset)? {
+                0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+                read => {
+                    buf = &mut buf[read..];
+                    offset += read as u64;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An archive that reads only what it needs from its source. Opening it reads
+/// the header, the entry table and the directory, then each file is read in a
+/// single request when it's asked for.
+#[derive(Debug)]
+pub struct LazyArchive<R> {
+    source: R,
+    entries: Vec<Entry>,
+    // Where the data of each entry ends, the start of the next one's
+    ends: Vec<u64>,
+    filenames: Vec<String>,
+    index: HashMap<String, usize>,
+    inflater: Arc<dyn Inflate>,
+    max_blocks: usize,
+    timestamp: Option<u32>,
+}
+
+/// What an archive holds, from reading only the header, entry table, footer
+/// and directory, so listing every archive in an install doesn't read any of
+/// their files
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ArchivePeek {
+    /// Names and uncompressed sizes, in the order of their data
+    pub files: Vec<(String, u32)>,
+    /// Seconds since the Unix epoch from the footer, if it has one
+    pub timestamp: Option<u32>,
+}
+
+#[cfg(feature = "fs")]
+pub fn peek(path: impl AsRef<Path>) -> Result<ArchivePeek, Error> {
+    Ok(LazyArchive::open(File::open(path)?)?.peek())
+}
+
+impl<R: RandomAccess> LazyArchive<R> {
+    pub fn open(source: R) -> Result<Self, Error> {
+        Self::open_with(source, &ParseOptions::default())
+    }
+
+    /// Only `inflater`, `encoding` and `limits` are used, damaged archives can't
+    /// be recovered
+    pub fn open_with(source: R, options: &ParseOptions) -> Result<Self, Error> {
+        let inflater = options.inflater.clone().unwrap_or_else(|| Arc::new(Zlib));
+
+        let mut head = [0; HEADER_SIZE as usize];
+        source.read_at(0, &mut head)?;
+        check_format(&head)?;
+        let (_, header) =
+            parser::header(&head).map_err(|e| Error::parser(Section::Header, &head, 0, e))?;
+
+        let size = source.size()?;
+        let index_start = u64::from(header.pointer);
+        let index_size = size.checked_sub(index_start).ok_or(Error::TruncatedBlock {
+            offset: header.pointer as usize,
+        })?;
+        let mut tail = buffer(index_size)?;
+        source.read_at(index_start, &mut tail)?;
+        let (_, (_, mut entries, footer)) = entry_table(&tail)
+            .map_err(|e| Error::parser(Section::EntryTable, &tail, header.pointer as usize, e))?;
+        options.limits.check_entries(&entries)?;
+
+        // Each file's data runs up to the next pointer after its own that any
+        // file with data has. Files sharing blocks share a pointer, so it has
+        // to be strictly after. Empty entries have no blocks, so they're
+        // skipped wherever they point, which can be the next file, an empty
+        // block or even past the end of the data.
+        let mut starts: Vec<u64> = entries
+            .iter()
+            .filter(|entry| entry.uncompressed_size > 0)
+            .map(|entry| u64::from(entry.pointer))
+            .filter(|&pointer| pointer < index_start)
+            .collect();
+        starts.sort_unstable();
+        starts.dedup();
+        let mut ends: Vec<u64> = entries
+            .iter()
+            .map(|entry| {
+                let pointer = u64::from(entry.pointer);
+                let next = starts.partition_point(|&start| start <= pointer);
+                starts.get(next).copied().unwrap_or(index_start)
+            })
+            .collect();
+
+        let mut archive = Self {
+            source,
+            entries: Vec::new(),
+            ends: Vec::new(),
+            filenames: Vec::new(),
+            index: HashMap::new(),
+            inflater,
+            max_blocks: options.limits.max_blocks,
+            timestamp: footer.map(|footer| footer.timestamp()),
+        };
+
+        let mut directory = None;
+        for position in directory_positions(&entries).into_iter().rev() {
+            directory = Some((entries.remove(position), ends.remove(position)));
+        }
+        let listed = match directory {
+            Some((directory, end)) => {
+                let names = archive.fetch(&directory, end)?;
+                parser::read_directory(&names)?
+            }
+            None => Vec::new(),
+        };
+        let names = associate(&entries, listed);
+        for (entry, name) in entries.iter_mut().zip(names) {
+            entry.filename = match name {
+                Some(name) => options.encoding.decode(&name),
+                None => unnamed(entry.filename_crc),
+            };
+        }
+        archive.filenames = entries.iter().map(|entry| entry.filename.clone()).collect();
+        archive.index = index(&archive.filenames);
+        archive.entries = entries;
+        archive.ends = ends;
+        Ok(archive)
+    }
+
+    pub fn filenames(&self) -> &[String] {
+        &self.filenames
+    }
+
+    pub fn contains(&self, filename: &str) -> bool {
+        self.index.contains_key(&normalize_filename(filename))
+    }
+
+    pub fn get(&self, filename: &str) -> Result<Option<Vec<u8>>, Error> {
+        match self.index.get(&normalize_filename(filename)) {
+            Some(&position) => self
+                .fetch(&self.entries[position], self.ends[position])
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn peek(&self) -> ArchivePeek {
+        ArchivePeek {
+            files: self
+                .filenames
+                .iter()
+                .zip(&self.entries)
+                .map(|(filename, entry)| (filename.clone(), entry.uncompressed_size))
+                .collect(),
+            timestamp: self.timestamp,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.source
+    }
+
+    // Reads all of an entry's blocks at once and inflates them. Offsets in
+    // any error are from the start of the entry rather than the archive.
+    fn fetch(&self, entry: &Entry, end: u64) -> Result<Vec<u8>, Error> {
+        if entry.uncompressed_size == 0 {
+            return Ok(Vec::new());
+        }
+        let start = u64::from(entry.pointer);
+        let size = end.checked_sub(start).ok_or(Error::TruncatedBlock {
+            offset: entry.pointer as usize,
+        })?;
+        let mut data = buffer(size)?;
+        self.source.read_at(start, &mut data)?;
+
+        let mut local = Entry {
+            filename: String::new(),
+            filename_crc: entry.filename_crc,
+            pointer: 0,
+            uncompressed_size: entry.uncompressed_size,
+            blocks: Vec::new(),
+        };
+        local.blocks = blocks(&data, &local, self.max_blocks)?;
+        local.decompress(&data, &*self.inflater)
+    }
+}
+
+// Sizes come from the source as u64, which a 32 bit target can't always hold
+fn buffer(size: u64) -> Result<Vec<u8>, Error> {
+    match usize::try_from(size) {
+        Ok(size) => Ok(vec![0; size]),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::FileTooLarge,
+            format!("{} bytes can't be held in memory on this target", size),
+        )
+        .into()),
+    }
+}
@@ -1,101 +1,1037 @@
 //! # An Everquest archive file extractor
-//! This has only been tested on .s3d files and implements only the bare minimum of functionality.
-//! CRC checks for example are completely ignored.
+//! This has been tested on .s3d and .eqg files and implements only the bare minimum of functionality.
+//! CRC checks for example are skipped when reading, use [`Archive::verify`] to check them.
 //!
 //! # Examples
-//! ```rust
+//! ```rust,no_run
 //! let archive = eq_archive::read("gfaydark.s3d").unwrap();
 //!
 //! // List all files in the archive
 //! let filenames = archive.filenames();
 //!
-//! // Iterate over files in the archive
-//! for (name, data) in archive.files() {
+//! // Check the stored filename CRCs
+//! for mismatch in archive.verify() {
+//!     println!("{} has a bad CRC", mismatch.filename);
+//! }
+//!
+//! // Find the zone's .wld and all of its textures
+//! let wld = archive.wld().unwrap();
+//! let textures = archive.files_with_extension("bmp");
+//! let trees = archive.glob("tree*.bmp");
+//!
+//! // Inflate only the files that are needed
+//! let grass = archive.get_many(&["grass.bmp", "grass2.bmp"]).unwrap();
+//!
+//! // Stream a single file without inflating it all at once
+//! let mut reader = archive.get_reader("gfaydark.wld").unwrap();
+//! std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
+//!
+//! // Or only inflate the part that's needed
+//! let wld_header = archive.read_range("gfaydark.wld", 0, 28).unwrap();
+//!
+//! // Checksum every file, to compare against another install
+//! let manifest = archive.manifest().unwrap();
+//!
+//! // Or alongside the sizes, also checking each block against zlib's checksum
+//! let checked = archive.entries_info_with_checksums(true).unwrap();
+//!
+//! // Or cheaper still, to tell whether files extracted earlier are stale
+//! let fingerprint = archive.fingerprint();
+//!
+//! // See where the space goes: sizes per extension, the 10 largest files
+//! // and files stored more than once under different names
+//! let analysis = archive.analyze(10).unwrap();
+//! println!("{} bytes duplicated", analysis.duplicate_size());
+//!
+//! // Write every file out to a directory
+//! archive.extract_all(std::path::Path::new("gfaydark")).unwrap();
+//!
+//! // Iterate over files in the archive, inflating them as they're reached
+//! for file in archive.iter_files() {
+//!     let (name, data) = file.unwrap();
+//! }
+//!
+//! // That's the order of their data, or they can follow the directory instead
+//! for file in archive.iter_directory_order().unwrap() {
+//!     let (name, data) = file.unwrap();
+//! }
+//!
+//! // Or along with what kind of file each one is
+//! for file in archive.classified_files() {
+//!     if let (name, eq_archive::FileKind::Wld, data) = file.unwrap() {
+//!         println!("{} is a {} byte world", name, data.len());
+//!     }
+//! }
+//!
+//! // Or inflate every file up front
+//! for (name, data) in archive.files().unwrap() {
 //!
 //! }
 //!
 //! ```
 //!
+//! Damaged archives can be partially recovered. Names read before any damage to
+//! the directory are kept and the rest are named after their CRC.
+//! ```rust,no_run
+//! let options = eq_archive::ParseOptions {
+//!     recover: true,
+//!     ..Default::default()
+//! };
+//! let archive = eq_archive::read_with("gfaydark.s3d", &options).unwrap();
+//! for failure in archive.failures() {
+//!     println!("could not read {}: {}", failure.filename, failure.error);
+//! }
+//!
+//! // Or checked before being shipped to players
+//! let options = eq_archive::ParseOptions {
+//!     strict: true,
+//!     ..Default::default()
+//! };
+//! eq_archive::read_with("gfaydark.s3d", &options).unwrap();
+//!
+//! // Or have every problem reported instead of only the first
+//! for issue in eq_archive::read("gfaydark.s3d").unwrap().check().issues {
+//!     println!("{:?}: {}", issue.severity, issue.error);
+//! }
+//! ```
+//!
+//! The layout on disk can be read with the same parsers the crate uses
+//! ```rust,no_run
+//! use eq_archive::raw;
+//!
+//! let data = std::fs::read("gfaydark.s3d").unwrap();
+//! let (_, header) = raw::header(&data).unwrap();
+//! let (_, (_, entries, _)) = raw::entry_table(&data[header.pointer() as usize..]).unwrap();
+//! for entry in entries {
+//!     println!("{:#010x} at {:#x}", entry.crc(), entry.pointer());
+//! }
+//! ```
+//!
+//! Files that aren't archives can be skipped by checking only their header
+//! ```rust,no_run
+//! for entry in std::fs::read_dir(".").unwrap() {
+//!     let path = entry.unwrap().path();
+//!     if eq_archive::sniff_file(&path).unwrap_or(false) {
+//!         println!("{} is an archive", path.display());
+//!     }
+//! }
+//!
+//! // Or find which archives in a directory have a file, reading only their directories
+//! for (path, filename) in eq_archive::search("C:/EverQuest", "*orc*.bmp").unwrap() {
+//!     println!("{} is in {}", filename, path.display());
+//! }
+//! ```
+//!
+//! Archives loaded from memory borrow the input rather than copying it
+//! ```rust,no_run
+//! let data = std::fs::read("gfaydark.s3d").unwrap();
+//! let archive = eq_archive::load(&data).unwrap();
+//!
+//! // Take ownership of the data if the archive needs to outlive the input
+//! let archive: eq_archive::Archive<'static> = archive.into_owned();
+//!
+//! // Or read one from anything that implements `Read`
+//! let archive = eq_archive::from_reader(std::io::stdin()).unwrap();
+//!
+//! // Files holding several archives back to back are read with `load_all`
+//! let archives = eq_archive::load_all(&data).unwrap();
+//! ```
+//!
+//! Archives that are somewhere slow to read, like a web server, only need to be
+//! read where the directory and the requested files are
+//! ```rust,no_run
+//! let file = std::fs::File::open("gfaydark.s3d").unwrap();
+//! let archive = eq_archive::LazyArchive::open(file).unwrap();
+//! let wld = archive.get("gfaydark.wld").unwrap();
+//!
+//! // Or just list what's in one without reading any of its files
+//! for (filename, size) in eq_archive::peek("gfaydark.s3d").unwrap().files {
+//!     println!("{} {}", filename, size);
+//! }
+//! ```
+//!
+//! Archives can also be created from scratch
+//! ```rust,no_run
+//! let mut builder = eq_archive::ArchiveBuilder::new();
+//! builder.add_file("readme.txt", b"Hello Norrath!".to_vec());
+//!
+//! // Names the client can't look up can be fixed or rejected before adding
+//! let name = eq_archive::names::normalize("Textures\\Grass.BMP").unwrap();
+//! builder.add_file(&name, std::fs::read("Textures/Grass.BMP").unwrap());
+//! builder.write_to("custom.s3d").unwrap();
+//!
+//! // Or keep it in memory, the same files always give the same bytes
+//! let bytes = builder.in_memory().unwrap();
+//! let archive = eq_archive::Archive::from_bytes(bytes).unwrap();
+//!
+//! // Trade speed for size when writing, and sort files by name
+//! let options = eq_archive::WriteOptions {
+//!     compression: eq_archive::Compression::Best,
+//!     ordering: eq_archive::FileOrder::Name,
+//!     ..Default::default()
+//! };
+//! let mut builder = eq_archive::ArchiveBuilder::with_options(options);
+//!
+//! // Or lay the archive out exactly like the retail packer for older clients
+//! let mut builder = eq_archive::ArchiveBuilder::with_options(eq_archive::WriteOptions::retail());
+//!
+//! // Or for .eqg files, which newer clients expect without a footer
+//! let mut builder = eq_archive::ArchiveBuilder::with_options(eq_archive::WriteOptions::eqg());
+//!
+//! // Or stream files straight to disk without holding them in memory
+//! let file = std::fs::File::create("client.s3d").unwrap();
+//! let mut writer = eq_archive::ArchiveWriter::new(file).unwrap();
+//! writer.start_file("gfaydark.wld").unwrap();
+//! std::io::copy(&mut std::fs::File::open("gfaydark.wld").unwrap(), &mut writer).unwrap();
+//! writer.finish().unwrap();
+//! ```
+//!
+//! Files in an existing archive can be changed and saved back out
+//! ```rust,no_run
+//! let mut archive = eq_archive::read("gfaydark.s3d").unwrap();
+//! archive.replace("grass.bmp", std::fs::read("grass.bmp").unwrap()).unwrap();
+//! archive.rename("tree.bmp", "oak.bmp").unwrap();
+//! archive.save("gfaydark.s3d").unwrap();
+//!
+//! // Append changes after the existing data instead of recompressing every file
+//! archive.set_edit_mode(eq_archive::EditMode::Append);
+//! archive.replace("grass.bmp", std::fs::read("grass.bmp").unwrap()).unwrap();
+//!
+//! // Pick up changes made by another program, only parsing again if there are any
+//! if archive.reload_if_changed("gfaydark.s3d").unwrap() {
+//!     println!("gfaydark.s3d changed");
+//! }
+//!
+//! // Keep notes for other tools inside the archive
+//! archive.set_metadata(r#"{"packer": "zonetool 1.2"}"#).unwrap();
+//!
+//! // Overwrite a single file on disk without rebuilding the archive if it still fits
+//! let grass = std::fs::read("grass.bmp").unwrap();
+//! let options = eq_archive::WriteOptions::default();
+//! archive
+//!     .patch_in_place("gfaydark.s3d", "grass.bmp", grass, &options)
+//!     .unwrap();
+//!
+//! // Shrink an archive for downloading by compressing it harder
+//! let options = eq_archive::WriteOptions {
+//!     compression: eq_archive::Compression::Best,
+//!     ..Default::default()
+//! };
+//! let report = archive.repack(&options).unwrap();
+//! println!("saved {} bytes", report.saved());
+//! ```
+//!
+//! Related archives can be mounted together and read as one
+//! ```rust,no_run
+//! let zone = eq_archive::ArchiveSet::read_zone(std::path::Path::new("."), "gfaydark").unwrap();
+//! let trees = zone.get("tree.bmp").unwrap();
+//!
+//! // Including .eqg companions, starting from any one of the zone's archives
+//! let zone = eq_archive::related("gfaydark_obj.s3d").unwrap();
+//!
+//! // Files in archives mounted later take precedence
+//! let mut set = eq_archive::ArchiveSet::new();
+//! set.mount(eq_archive::read("gfaydark.s3d").unwrap())
+//!     .mount(eq_archive::read("patch.s3d").unwrap());
+//!
+//! // Or be merged into a single archive
+//! let merged = eq_archive::Archive::merge(set.archives(), eq_archive::Conflict::KeepLast).unwrap();
+//! let (textures, rest) = merged.split(|filename| filename.ends_with(".bmp")).unwrap();
+//! ```
+//!
+//! Archives, sets of them and extracted files can all be loaded from through `Vfs`
+//! ```rust,no_run
+//! use eq_archive::Vfs;
+//!
+//! let assets: Box<dyn Vfs> = if std::path::Path::new("gfaydark").is_dir() {
+//!     Box::new(eq_archive::LooseFiles::new("gfaydark"))
+//! } else {
+//!     Box::new(eq_archive::read("gfaydark.s3d").unwrap())
+//! };
+//! let grass = assets.read("grass.bmp").unwrap();
+//! ```
+//!
+//! Archives shared between threads cache recently decompressed files
+//! ```rust,no_run
+//! let archive = std::sync::Arc::new(eq_archive::SharedArchive::new(
+//!     eq_archive::read("gfaydark.s3d").unwrap(),
+//! ));
+//! let handle = {
+//!     let archive = archive.clone();
+//!     std::thread::spawn(move || archive.get("grass.bmp").unwrap())
+//! };
+//! let grass = archive.get("grass.bmp").unwrap();
+//! handle.join().unwrap();
+//!
+//! // Or decompress as much as fits in the cache up front
+//! archive.prefetch().unwrap();
+//!
+//! // Or just the files about to be needed, in the background
+//! let prefetching = archive.prefetch_files(&["grass.bmp", "gfaydark.wld"]);
+//! prefetching.join().unwrap().unwrap();
+//! ```
+//!
+//! Long running operations can report their progress
+//! ```rust,no_run
+//! let options = eq_archive::ParseOptions {
+//!     progress: Some(|event| println!("{:?}", event)),
+//!     ..Default::default()
+//! };
+//! let archive = eq_archive::read_with("gfaydark.s3d", &options).unwrap();
+//! archive.extract_all(std::path::Path::new("gfaydark")).unwrap();
+//! ```
+//!
+//! With the `tracing` feature reading and decompressing are instrumented: a
+//! span for each entry, block counts and sizes at `debug`, and skipped entries
+//! and CRC mismatches as warnings. The command line tool prints them when run
+//! with `RUST_LOG=debug`.
+//!
+//! # Features
+//! - `fs` (default): Read and write archives on the filesystem, without it the crate
+//!   only works with archives in memory and builds for `wasm32-unknown-unknown`
+//! - `mmap`: Memory map archives with `open_mmap` instead of reading them into memory
+//! - `parallel`: Decompress files across multiple threads with `Archive::files_par` and `Archive::get_many_par`,
+//!   and compress files and their blocks across `WriteOptions::threads` when writing
+//! - `async`: Read archives with `read_async` and stream their files with `Archive::files_stream`
+//! - `bevy`: Serve an archive's files as a bevy asset source with `ArchiveAssetReader`,
+//!   or load whole archives as assets with `ArchiveLoader`
+//! - `rust_backend` (default), `zlib-rs`, `zlib-ng`: Choose the zlib implementation used by flate2,
+//!   `benches/archive.rs` measures the difference
+//! - `regex`: Select files with a regular expression using `Archive::matching`
+//! - `ffi`: C bindings in the `ffi` module, declared in `include/eq_archive.h`. Python
+//!   bindings built on them are in `python/eq_archive.py`
+//! - `hash`: SHA-256 of whole archives with `Archive::hash`, or with `hash_file` straight
+//!   from disk without parsing them. Also recorded in `Manifest::archive`, and names the
+//!   directories of `ExtractOptions::cache`, which later runs copy files from instead of inflating
+//! - `serde`: Serialize and deserialize the `Manifest` from `Archive::manifest`
+//! - `zip`: Convert archives to and from zip files with `Archive::to_zip` and `Archive::from_zip`
+//! - `textures`: Find BMP, DDS and TGA textures and their dimensions with `Archive::textures`
+//! - `sounds`: Find WAV, XMI and MIDI files in snd*.pfs archives with `Archive::sounds`
+//! - `testing`: Builders for synthetic archives in the `testing` module, for use as test fixtures
+//! - `tracing`: Spans and events from parsing and decompressing through `tracing`, or `log`
+//!   when no subscriber is installed
+//! - `cli`: Build the `eq-archive` binary for listing, extracting, packing, checking and analyzing archives
+//! - `fuse`: Build `examples/mount.rs`, which mounts an archive as a read-only directory on Linux
+//!
+
+// Without the tracing feature events compile to nothing, their arguments are
+// still type checked so they don't rot
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
 
+mod archive_writer;
+#[cfg(feature = "async")]
+mod async_io;
+#[cfg(feature = "bevy")]
+mod bevy_io;
+mod buffer;
+mod check;
+mod classify;
+pub mod crc;
+mod diff;
+mod edit;
+mod error;
+#[cfg(feature = "fs")]
+mod extract;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod hash;
+mod inflate;
+mod lazy;
+mod manifest;
+mod merge;
+mod metadata;
+pub mod names;
 mod parser;
+mod progress;
+pub mod raw;
+mod reader;
+#[cfg(feature = "fs")]
+mod reload;
+mod repack;
+mod select;
+mod set;
+mod shared;
+#[cfg(feature = "sounds")]
+mod sound;
+mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "textures")]
+mod texture;
+mod vfs;
+mod writer;
+#[cfg(feature = "zip")]
+mod zip_io;
 
-pub use parser::{Archive, Entry};
+pub use archive_writer::ArchiveWriter;
+#[cfg(feature = "async")]
+pub use async_io::read_async;
+#[cfg(feature = "bevy")]
+pub use bevy_io::{ArchiveAsset, ArchiveAssetReader, ArchiveLoader};
+pub use check::{IntegrityReport, Issue, Severity};
+pub use classify::FileKind;
+pub use diff::{diff, ArchiveDiff};
+pub use edit::EditMode;
+pub use error::{Error, Section};
+#[cfg(all(feature = "fs", feature = "hash"))]
+pub use extract::CacheConfig;
+#[cfg(feature = "fs")]
+pub use extract::ExtractOptions;
+#[cfg(all(feature = "hash", feature = "fs"))]
+pub use hash::hash_file;
+#[cfg(feature = "hash")]
+pub use hash::ArchiveHash;
+pub use hash::Fingerprint;
+pub use inflate::{Inflate, Zlib};
+#[cfg(feature = "fs")]
+pub use lazy::peek;
+pub use lazy::{ArchivePeek, LazyArchive, RandomAccess};
+pub use manifest::{Manifest, ManifestEntry};
+pub use merge::Conflict;
+pub use metadata::METADATA_FILENAME;
+pub use parser::{
+    normalize_filename, Archive, Block, Encoding, Entry, Failure, Format, Header, Limits,
+    LookupOptions, ParseOptions, Version, PFS_MAGIC, SUPPORTED_VERSIONS,
+};
+pub use progress::{Progress, ProgressEvent};
+pub use reader::{EntryFile, EntryReader};
+pub use repack::RepackReport;
+#[cfg(all(feature = "fs", any(unix, windows)))]
+pub use select::search;
+#[cfg(feature = "fs")]
+pub use set::related;
+pub use set::ArchiveSet;
+pub use shared::SharedArchive;
+#[cfg(feature = "sounds")]
+pub use sound::{PcmFormat, Sound, SoundFormat, SoundInfo};
+pub use stats::{Analysis, ArchiveStats, Duplicates, ExtensionStats};
+#[cfg(feature = "textures")]
+pub use texture::{Texture, TextureFormat, TextureInfo};
+#[cfg(feature = "fs")]
+pub use vfs::LooseFiles;
+pub use vfs::Vfs;
+pub use writer::{
+    ArchiveBuilder, Compression, FileOrder, WriteOptions, DETERMINISTIC_TIMESTAMP, MAX_BLOCK_SIZE,
+};
 
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "fs")]
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use flate2::read::ZlibDecoder;
-use nom::error::ErrorKind;
+use progress::Tracker;
+#[cfg(feature = "tracing")]
+use tracing::{debug, warn};
 
-impl Archive {
-    pub fn filenames(&self) -> Vec<String> {
-        let directory = self.entries.last().expect("Directory block does not exist");
-        let uncompressed_blocks = directory.decompress();
-        let (_, filenames) =
-            parser::directory(&uncompressed_blocks[..]).expect("Failed to parse directory block");
-        filenames
+impl Archive<'static> {
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        parser::parse(data.into())
     }
+}
 
-    pub fn get(&self, filename: &str) -> Option<Vec<u8>> {
-        self.filenames()
-            .iter()
-            .position(|f| f.eq_ignore_ascii_case(filename))
-            .and_then(|position| self.entries.get(position).map(|entry| entry.decompress()))
+impl<'a> Archive<'a> {
+    /// Always one of `SUPPORTED_VERSIONS`, anything else is rejected when reading
+    pub fn version(&self) -> Version {
+        self.header.version()
     }
 
-    pub fn files(self) -> impl Iterator<Item = (String, Vec<u8>)> {
-        self.filenames()
-            .into_iter()
-            .zip(self.entries.into_iter().map(|entry| entry.decompress()))
+    pub fn header(&self) -> &Header {
+        &self.header
     }
-}
 
-impl Entry {
-    fn decompress(&self) -> Vec<u8> {
-        self.blocks
+    /// Entries that were skipped when reading with `ParseOptions::recover`
+    pub fn failures(&self) -> &[Failure] {
+        &self.failures
+    }
+
+    /// When the archive was written, archives without a footer don't record it
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.footer
             .as_ref()
-            .expect("Failed to decompress block")
+            .map(|footer| UNIX_EPOCH + Duration::from_secs(footer.timestamp.into()))
+    }
+
+    /// Replaces the hook given in `ParseOptions::progress`
+    pub fn set_progress(&mut self, progress: Option<Progress>) {
+        self.progress = progress;
+    }
+
+    /// Replaces `ParseOptions::lookup`
+    pub fn set_lookup(&mut self, lookup: LookupOptions) {
+        self.lookup = lookup;
+    }
+
+    /// Files are listed in the order of their data in the archive, which for
+    /// every known packer is also the order of the directory, see
+    /// `iter_directory_order` for the others. Empty files
+    /// share a position with the file after them and are ordered by CRC.
+    /// Names keep their case from the directory whichever way lookups match,
+    /// see `normalize_filename` for keys that ignore it.
+    pub fn filenames(&self) -> &[String] {
+        &self.filenames
+    }
+
+    /// Undecoded filenames in the same order as `filenames`, for writing names
+    /// back out exactly as they were read
+    pub fn raw_filenames(&self) -> &[Vec<u8>] {
+        &self.raw_filenames
+    }
+
+    pub fn contains(&self, filename: &str) -> bool {
+        self.index_of(filename).is_some()
+    }
+
+    pub fn index_of(&self, filename: &str) -> Option<usize> {
+        let position = self.index.get(&normalize_filename(filename)).copied();
+        if !self.lookup.case_sensitive {
+            return position;
+        }
+        // Only the first of names differing in case is indexed
+        match position {
+            Some(position) if self.filenames[position] == filename => Some(position),
+            Some(_) => self.filenames.iter().position(|name| name == filename),
+            None => None,
+        }
+    }
+
+    /// For matching entries to names from somewhere other than the directory,
+    /// see `crc::filename_crc`
+    pub fn entry_by_crc(&self, crc: u32) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.filename_crc == crc)
+    }
+
+    /// The entry holding the directory of filenames, which isn't one of the
+    /// files. Archives without one only have unnamed files.
+    pub fn directory_entry(&self) -> Option<&Entry> {
+        self.directory.as_ref()
+    }
+
+    pub fn get(&self, filename: &str) -> Result<Option<Vec<u8>>, Error> {
+        self.entry(filename)
+            .map(|entry| self.decompress(entry))
+            .transpose()
+    }
+
+    /// Results are in the same order as the filenames asked for
+    pub fn get_many(&self, filenames: &[&str]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        filenames
             .iter()
-            .flat_map(|block| {
-                let mut buf = Vec::new();
-                ZlibDecoder::new(&block.data[..])
-                    .read_to_end(&mut buf)
-                    .expect("Failed to decompress block");
-                buf
+            .map(|filename| self.get(filename))
+            .collect()
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn get_many_par(&self, filenames: &[&str]) -> Result<Vec<Option<Vec<u8>>>, Error> {
+        use rayon::prelude::*;
+
+        filenames
+            .par_iter()
+            .map(|filename| self.get(filename))
+            .collect()
+    }
+
+    /// The zlib stream of each block one after another, without inflating
+    /// them. `Entry::blocks` gives the size of each stream.
+    pub fn get_raw(&self, filename: &str) -> Option<Vec<u8>> {
+        self.entry(filename)
+            .map(|entry| entry.compressed_bytes(&self.data))
+    }
+
+    pub fn get_reader(&self, filename: &str) -> Option<EntryReader<'_>> {
+        self.entry(filename)
+            .map(|entry| EntryReader::new(&self.data, &entry.blocks, &*self.inflater))
+    }
+
+    pub fn open(&self, filename: &str) -> Option<EntryFile<'_>> {
+        self.entry(filename)
+            .map(|entry| EntryFile::new(&self.data, &entry.blocks, &*self.inflater))
+    }
+
+    /// Only the blocks covering the range are inflated. The range is cut short
+    /// at the end of the file.
+    pub fn read_range(
+        &self,
+        filename: &str,
+        offset: u64,
+        len: usize,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let mut file = match self.open(filename) {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        let available = file.len().saturating_sub(offset);
+        let mut buf = Vec::with_capacity(available.min(len as u64) as usize);
+        file.seek(SeekFrom::Start(offset))?;
+        file.take(len as u64).read_to_end(&mut buf)?;
+        Ok(Some(buf))
+    }
+
+    pub(crate) fn decompress(&self, entry: &Entry) -> Result<Vec<u8>, Error> {
+        entry.decompress(&self.data, &*self.inflater)
+    }
+
+    // Replaces this archive with one parsed from `buffer`, once an edit has
+    // written it out. Settings made on this archive carry over to the new one.
+    pub(crate) fn reparse(&mut self, buffer: Vec<u8>) -> Result<(), Error> {
+        let options = ParseOptions {
+            inflater: Some(self.inflater.clone()),
+            encoding: self.encoding,
+            lookup: self.lookup,
+            ..Default::default()
+        };
+        let (edit_mode, progress) = (self.edit_mode, self.progress);
+        *self = parser::parse_with(buffer.into(), &options)?;
+        self.edit_mode = edit_mode;
+        self.progress = progress;
+        Ok(())
+    }
+
+    fn entry(&self, filename: &str) -> Option<&Entry> {
+        self.index_of(filename)
+            .and_then(|position| self.entries.get(position))
+    }
+
+    // Whether the entry at `position` has a name made up for it because the
+    // directory doesn't list it
+    pub(crate) fn is_unnamed(&self, position: usize) -> bool {
+        let entry = &self.entries[position];
+        self.raw_filenames[position] == parser::unnamed(entry.filename_crc).into_bytes()
+    }
+
+    pub fn verify(&self) -> Vec<CrcMismatch> {
+        self.filenames
+            .iter()
+            .zip(self.raw_filenames.iter())
+            .zip(self.entries.iter())
+            .enumerate()
+            .filter_map(|(position, ((filename, raw_filename), entry))| {
+                // Names made up for unlisted entries don't have a CRC to check
+                if self.is_unnamed(position) {
+                    return None;
+                }
+                let expected = crc::filename_crc(raw_filename);
+                if entry.filename_crc == expected {
+                    None
+                } else {
+                    warn!(
+                        "{} has CRC {:#010x} but its entry has {:#010x}",
+                        filename, expected, entry.filename_crc
+                    );
+                    Some(CrcMismatch {
+                        filename: filename.clone(),
+                        expected,
+                        found: entry.filename_crc,
+                    })
+                }
             })
             .collect()
     }
+
+    pub fn entries_info(&self) -> Vec<EntryInfo> {
+        let timestamp = self.footer.as_ref().map(|footer| footer.timestamp);
+        self.filenames
+            .iter()
+            .zip(self.entries.iter())
+            .map(|(filename, entry)| EntryInfo {
+                filename: filename.clone(),
+                compressed_size: entry.blocks.iter().map(|block| block.compressed_size).sum(),
+                uncompressed_size: entry.uncompressed_size,
+                block_count: entry.blocks.len(),
+                crc: entry.filename_crc,
+                timestamp,
+                content_checksum: None,
+            })
+            .collect()
+    }
+
+    /// Like `entries_info` with `content_checksum` filled in, which means
+    /// inflating every file. Zlib streams end with an Adler-32 of their data,
+    /// `verify_adler32` checks those too for inflaters that don't, failing
+    /// with `Error::ChecksumMismatch`.
+    pub fn entries_info_with_checksums(
+        &self,
+        verify_adler32: bool,
+    ) -> Result<Vec<EntryInfo>, Error> {
+        let mut infos = self.entries_info();
+        let mut tracker = Tracker::new(self.progress, Some(self.entries.len()));
+        for (info, entry) in infos.iter_mut().zip(self.entries.iter()) {
+            let contents = self.decompress(entry)?;
+            if verify_adler32 {
+                entry.verify_adler32(&self.data, &contents)?;
+            }
+            info.content_checksum = Some(crc32fast::hash(&contents));
+            tracker.advance(contents.len() as u64);
+        }
+        Ok(infos)
+    }
+
+    pub fn files(self) -> Result<impl Iterator<Item = (String, Vec<u8>)>, Error> {
+        let (data, inflater) = (&self.data, &*self.inflater);
+        let mut tracker = Tracker::new(self.progress, Some(self.entries.len()));
+        let files = self
+            .filenames
+            .into_iter()
+            .zip(self.entries.iter())
+            .map(|(name, entry)| {
+                let contents = entry.decompress(data, inflater)?;
+                tracker.advance(contents.len() as u64);
+                Ok((name, contents))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(files.into_iter())
+    }
+
+    /// Each file is only decompressed once the iterator reaches it, so only one
+    /// is held at a time and a bad entry doesn't stop the ones after it
+    pub fn iter_files(&self) -> impl Iterator<Item = Result<(String, Vec<u8>), Error>> + '_ {
+        self.iter_positions(0..self.entries.len())
+    }
+
+    /// Files in the order of their data, reading the archive front to back,
+    /// for callers that rely on it like streaming from slow disks or rewriting
+    /// an archive byte for byte. Files sharing data keep their relative order.
+    pub fn iter_disk_order(&self) -> impl Iterator<Item = Result<(String, Vec<u8>), Error>> + '_ {
+        let mut positions: Vec<_> = (0..self.entries.len()).collect();
+        positions.sort_by_key(|&position| self.entries[position].pointer);
+        self.iter_positions(positions.into_iter())
+    }
+
+    /// Files in the order the directory lists them, which is what other tools
+    /// show. Files the directory doesn't name come last in disk order.
+    pub fn iter_directory_order(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<(String, Vec<u8>), Error>> + '_, Error> {
+        Ok(self.iter_positions(self.directory_order()?.into_iter()))
+    }
+
+    fn iter_positions<'s>(
+        &'s self,
+        positions: impl Iterator<Item = usize> + 's,
+    ) -> impl Iterator<Item = Result<(String, Vec<u8>), Error>> + 's {
+        let mut tracker = Tracker::new(self.progress, Some(self.entries.len()));
+        positions.map(move |position| {
+            let contents = self.decompress(&self.entries[position])?;
+            tracker.advance(contents.len() as u64);
+            Ok((self.filenames[position].clone(), contents))
+        })
+    }
+
+    // Positions of the entries in directory order. Names are matched by their
+    // raw bytes, so repeated names are taken in disk order.
+    fn directory_order(&self) -> Result<Vec<usize>, Error> {
+        let listed = match &self.directory {
+            Some(directory) => parser::read_directory(&self.decompress(directory)?)?,
+            None => Vec::new(),
+        };
+        let mut positions: HashMap<&[u8], VecDeque<usize>> = HashMap::new();
+        for (position, name) in self.raw_filenames.iter().enumerate() {
+            positions.entry(name).or_default().push_back(position);
+        }
+
+        let mut order = Vec::with_capacity(self.entries.len());
+        let mut ordered = vec![false; self.entries.len()];
+        for name in &listed {
+            if let Some(position) = positions
+                .get_mut(name.as_slice())
+                .and_then(VecDeque::pop_front)
+            {
+                order.push(position);
+                ordered[position] = true;
+            }
+        }
+        order.extend((0..self.entries.len()).filter(|&position| !ordered[position]));
+        Ok(order)
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn files_par(self) -> Result<impl Iterator<Item = (String, Vec<u8>)>, Error> {
+        use rayon::prelude::*;
+
+        let (data, inflater) = (&self.data, &*self.inflater);
+        let files = self
+            .filenames
+            .into_par_iter()
+            .zip(self.entries.par_iter())
+            .map(|(name, entry)| Ok((name, entry.decompress(data, inflater)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(files.into_iter())
+    }
 }
-#[derive(Debug)]
-pub enum Error {
-    IO(io::Error),
-    Parser,
+
+#[derive(Debug, PartialEq)]
+pub struct CrcMismatch {
+    pub filename: String,
+    pub expected: u32,
+    pub found: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryInfo {
+    pub filename: String,
+    pub compressed_size: u32,
+    pub uncompressed_size: u32,
+    pub block_count: usize,
+    pub crc: u32,
+    /// Taken from the archive footer, which is shared by every entry
+    pub timestamp: Option<u32>,
+    /// CRC32 of the inflated file, only from `entries_info_with_checksums`
+    pub content_checksum: Option<u32>,
 }
 
-impl From<io::Error> for Error {
-    fn from(e: io::Error) -> Self {
-        Self::IO(e)
+impl Entry {
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn crc(&self) -> u32 {
+        self.filename_crc
+    }
+
+    #[deprecated(note = "renamed to `crc`")]
+    pub fn filename_crc(&self) -> u32 {
+        self.crc()
+    }
+
+    /// Uncompressed size of the file
+    pub fn size(&self) -> u32 {
+        self.uncompressed_size
+    }
+
+    /// Size of the compressed data, not counting the block headers
+    pub fn compressed_size(&self) -> u32 {
+        self.blocks.iter().map(|block| block.compressed_size).sum()
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Offset of the header of the entry's first block
+    pub fn pointer(&self) -> u32 {
+        self.pointer
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Offsets in the archive of all of the entry's blocks including their
+    /// headers, which are stored back to back. Delta patching tools can cut
+    /// chunks on these so a changed file doesn't shift the ones around it.
+    /// Empty for files without any blocks.
+    pub fn data_span(&self) -> Range<u64> {
+        let start = u64::from(self.pointer);
+        match self.blocks.last() {
+            Some(last) => start..last.offset as u64 + u64::from(last.compressed_size),
+            None => start..start,
+        }
+    }
+
+    /// Takes the bytes of the archive the entry was read from, see
+    /// `Archive::as_bytes`, and panics if given anything shorter
+    pub fn compressed_bytes(&self, archive: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.compressed_size() as usize);
+        for block in &self.blocks {
+            bytes.extend_from_slice(block.data(archive));
+        }
+        bytes
+    }
+
+    // The start of the file, only inflating as many blocks as that takes
+    #[cfg(any(feature = "textures", feature = "sounds"))]
+    pub(crate) fn peek(
+        &self,
+        archive: &[u8],
+        inflater: &dyn Inflate,
+        size: u64,
+    ) -> Result<Vec<u8>, Error> {
+        let mut header = Vec::with_capacity(size as usize);
+        EntryReader::new(archive, &self.blocks, inflater)
+            .take(size)
+            .read_to_end(&mut header)?;
+        Ok(header)
+    }
+
+    pub(crate) fn decompress(
+        &self,
+        archive: &[u8],
+        inflater: &dyn Inflate,
+    ) -> Result<Vec<u8>, Error> {
+        self.decompress_from(archive, 0, inflater)
+    }
+
+    // Like `decompress`, but `data` starts `base` bytes into the archive
+    // rather than at the start, so only the entry's blocks need to be kept
+    pub(crate) fn decompress_from(
+        &self,
+        data: &[u8],
+        base: usize,
+        inflater: &dyn Inflate,
+    ) -> Result<Vec<u8>, Error> {
+        #[cfg(feature = "tracing")]
+        let _inflate = tracing::debug_span!("inflate", pointer = self.pointer).entered();
+        debug!(
+            "{} blocks to {} bytes",
+            self.blocks.len(),
+            self.uncompressed_size
+        );
+        // The stored size isn't trusted for preallocation, it could be anything
+        let mut buf = Vec::new();
+        for block in &self.blocks {
+            let start = buf.len();
+            let offset = block.offset - base;
+            let compressed = &data[offset..offset + block.compressed_size as usize];
+            block
+                .inflate_data(compressed, inflater, &mut buf)
+                .map_err(|source| Error::Decompress {
+                    offset: block.offset,
+                    source,
+                })?;
+            check_size(block.offset, block.uncompressed_size, buf.len() - start)?;
+        }
+        check_size(self.pointer as usize, self.uncompressed_size, buf.len())?;
+        Ok(buf)
+    }
+
+    // Takes what `decompress` returned. Stored blocks have no checksum.
+    pub(crate) fn verify_adler32(&self, archive: &[u8], contents: &[u8]) -> Result<(), Error> {
+        let mut start = 0;
+        for block in &self.blocks {
+            let end = start + block.uncompressed_size as usize;
+            let data = block.data(archive);
+            if !block.is_stored(archive) && data.len() >= 4 {
+                let trailer = &data[data.len() - 4..];
+                let expected = u32::from_be_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+                let actual = inflate::adler32(&contents[start..end]);
+                if actual != expected {
+                    return Err(Error::ChecksumMismatch {
+                        offset: block.offset,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+            start = end;
+        }
+        Ok(())
     }
 }
 
-impl From<nom::Err<(&[u8], ErrorKind)>> for Error {
-    fn from(_: nom::Err<(&[u8], ErrorKind)>) -> Self {
-        Self::Parser
+// Anything else means the data or the sizes stored for it are corrupt
+fn check_size(offset: usize, expected: u32, actual: usize) -> Result<(), Error> {
+    if actual == expected as usize {
+        Ok(())
+    } else {
+        Err(Error::SizeMismatch {
+            offset,
+            expected,
+            actual,
+        })
     }
 }
 
-pub fn read(filename: &str) -> Result<Archive, Error> {
-    let buffer = fill_buffer(filename)?;
-    Ok(parser::parse(&buffer[..])?)
+#[cfg(feature = "fs")]
+pub fn read(path: impl AsRef<Path>) -> Result<Archive<'static>, Error> {
+    read_with(path, &ParseOptions::default())
+}
+
+#[cfg(feature = "fs")]
+pub fn read_with(
+    path: impl AsRef<Path>,
+    options: &ParseOptions,
+) -> Result<Archive<'static>, Error> {
+    let mut file = File::open(path)?;
+    let stamp = reload::FileStamp::read(&mut file)?;
+    let mut archive = from_reader_with(file, options)?;
+    archive.stamp = Some(stamp);
+    Ok(archive)
 }
 
-pub fn load(data: &[u8]) -> Result<Archive, Error> {
-    Ok(parser::parse(&data[..])?)
+/// The whole archive is read into memory before it's parsed
+pub fn from_reader(reader: impl Read) -> Result<Archive<'static>, Error> {
+    from_reader_with(reader, &ParseOptions::default())
 }
 
-fn fill_buffer(filename: &str) -> io::Result<Vec<u8>> {
-    let mut file = File::open(filename)?;
+pub fn from_reader_with(
+    mut reader: impl Read,
+    options: &ParseOptions,
+) -> Result<Archive<'static>, Error> {
     let mut buffer = Vec::new();
-    file.read_to_end(&mut buffer)?;
-    Ok(buffer)
+    reader.read_to_end(&mut buffer)?;
+    parser::parse_with(buffer.into(), options)
+}
+
+pub use parser::sniff;
+
+#[cfg(feature = "fs")]
+pub fn sniff_file(path: impl AsRef<Path>) -> io::Result<bool> {
+    let mut header = Vec::with_capacity(parser::HEADER_SIZE as usize);
+    File::open(path)?
+        .take(parser::HEADER_SIZE.into())
+        .read_to_end(&mut header)?;
+    Ok(sniff(&header))
+}
+
+pub fn load(data: &[u8]) -> Result<Archive<'_>, Error> {
+    load_with(data, &ParseOptions::default())
+}
+
+pub fn load_with<'a>(data: &'a [u8], options: &ParseOptions) -> Result<Archive<'a>, Error> {
+    parser::parse_with(data.into(), options)
+}
+
+/// Some custom servers ship zones as several archives concatenated into one
+/// file. Each archive ends after its footer and anything following the last
+/// one that isn't an archive is ignored.
+pub fn load_all(data: &[u8]) -> Result<Vec<Archive<'_>>, Error> {
+    let mut archives = Vec::new();
+    let mut rest = data;
+    loop {
+        let (archive, next) = rest.split_at(parser::archive_len(rest)?);
+        archives.push(load(archive)?);
+        if !sniff(next) {
+            return Ok(archives);
+        }
+        rest = next;
+    }
+}
+
+#[cfg(feature = "fs")]
+pub fn read_all(path: impl AsRef<Path>) -> Result<Vec<Archive<'static>>, Error> {
+    let data = std::fs::read(path)?;
+    Ok(load_all(&data)?
+        .into_iter()
+        .map(Archive::into_owned)
+        .collect())
+}
+
+#[cfg(feature = "mmap")]
+pub fn open_mmap(path: impl AsRef<Path>) -> Result<Archive<'static>, Error> {
+    let mut file = File::open(path)?;
+    let stamp = reload::FileStamp::read(&mut file)?;
+    // Safety: the archive must not be modified by another process while it is mapped
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let mut archive = parser::parse(map.into())?;
+    archive.stamp = Some(stamp);
+    Ok(archive)
 }
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "hash")]
+use crate::ArchiveHash;
+use crate::{Archive, ArchiveDiff, Error};
+
+/// Checksums of every file in an archive, enough to tell which files differ
+/// between two installs without having either archive to hand
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Manifest {
+    pub files: Vec<ManifestEntry>,
+    /// The whole archive, for telling an unchanged one apart without
+    /// comparing every file. Missing from manifests written before it existed.
+    #[cfg(feature = "hash")]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub archive: Option<ArchiveHash>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ManifestEntry {
+    pub filename: String,
+    pub size: u32,
+    /// CRC32 of the uncompressed contents
+    pub crc32: u32,
+}
+
+impl Manifest {
+    /// Lists the files that would need to change to turn `self` into `other`
+    pub fn diff(&self, other: &Manifest) -> ArchiveDiff {
+        let (ours, theirs) = (self.lookup(), other.lookup());
+
+        let mut diff = ArchiveDiff::default();
+        for file in &self.files {
+            match theirs.get(&file.filename.to_ascii_lowercase()) {
+                Some(other) if other.size != file.size || other.crc32 != file.crc32 => {
+                    diff.changed.push(file.filename.clone())
+                }
+                Some(_) => {}
+                None => diff.removed.push(file.filename.clone()),
+            }
+        }
+        diff.added = other
+            .files
+            .iter()
+            .filter(|file| !ours.contains_key(&file.filename.to_ascii_lowercase()))
+            .map(|file| file.filename.clone())
+            .collect();
+        diff
+    }
+
+    // Filenames are compared case insensitively, like archive lookups
+    fn lookup(&self) -> HashMap<String, &ManifestEntry> {
+        self.files
+            .iter()
+            .map(|file| (file.filename.to_ascii_lowercase(), file))
+            .collect()
+    }
+}
+
+impl Archive<'_> {
+    pub fn manifest(&self) -> Result<Manifest, Error> {
+        let files = self
+            .filenames
+            .iter()
+            .zip(self.entries.iter())
+            .map(|(filename, entry)| {
+                let data = self.decompress(entry)?;
+                Ok(ManifestEntry {
+                    filename: filename.clone(),
+                    size: data.len() as u32,
+                    crc32: crc32fast::hash(&data),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Manifest {
+            files,
+            #[cfg(feature = "hash")]
+            archive: Some(self.hash()),
+        })
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use crate::writer::Name;
+use crate::{Archive, ArchiveBuilder, Error};
+
+/// What to do when more than one archive being merged holds the same file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Conflict {
+    KeepFirst,
+    /// Like `ArchiveSet`, so patches can be merged over a base install
+    #[default]
+    KeepLast,
+    Fail,
+}
+
+impl Archive<'_> {
+    /// Files keep the order they first appear in, the merged archive is stamped
+    /// with the newest timestamp of the archives
+    pub fn merge(archives: &[Archive], conflict: Conflict) -> Result<Archive<'static>, Error> {
+        let mut files = Vec::new();
+        let mut index = HashMap::new();
+
+        for archive in archives {
+            for (position, filename) in archive.filenames.iter().enumerate() {
+                let existing = index.get(&filename.to_ascii_lowercase()).copied();
+                match (existing, conflict) {
+                    (Some(_), Conflict::KeepFirst) => continue,
+                    (Some(_), Conflict::Fail) => {
+                        return Err(Error::FileExists(filename.clone()));
+                    }
+                    _ => {}
+                }
+                let file = archive.file(position)?;
+                match existing {
+                    Some(existing) => files[existing] = file,
+                    None => {
+                        index.insert(filename.to_ascii_lowercase(), files.len());
+                        files.push(file);
+                    }
+                }
+            }
+        }
+
+        let timestamp = archives.iter().filter_map(Archive::timestamp).max();
+        build(files, timestamp)
+    }
+
+    /// Splits the archive in two, the files `predicate` accepts and the rest.
+    /// Both keep this archive's timestamp.
+    pub fn split<F>(&self, mut predicate: F) -> Result<(Archive<'static>, Archive<'static>), Error>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let (mut matched, mut rest) = (Vec::new(), Vec::new());
+        for (position, filename) in self.filenames.iter().enumerate() {
+            let file = self.file(position)?;
+            if predicate(filename) {
+                matched.push(file);
+            } else {
+                rest.push(file);
+            }
+        }
+        Ok((
+            build(matched, self.timestamp())?,
+            build(rest, self.timestamp())?,
+        ))
+    }
+
+    fn file(&self, position: usize) -> Result<(Name, Vec<u8>), Error> {
+        Ok((
+            self.stored_name(position),
+            self.decompress(&self.entries[position])?,
+        ))
+    }
+}
+
+fn build(
+    files: Vec<(Name, Vec<u8>)>,
+    timestamp: Option<std::time::SystemTime>,
+) -> Result<Archive<'static>, Error> {
+    let mut builder = ArchiveBuilder::new();
+    for (name, data) in files {
+        builder.add_entry(name, data);
+    }
+    if let Some(timestamp) = timestamp {
+        builder.set_timestamp(timestamp);
+    }
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer)?;
+    Archive::from_bytes(buffer)
+}
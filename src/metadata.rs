@@ -0,0 +1,27 @@
+use crate::{Archive, ArchiveBuilder, Error};
+
+/// Reserved for tools to store their own data in an archive, like the packer
+/// version, hashes of the source files or build notes. The contents are up to
+/// the tool, JSON is suggested. It's stored as an ordinary file so the client
+/// ignores it and it's listed in `Archive::filenames`.
+pub const METADATA_FILENAME: &str = "eq_archive_metadata.json";
+
+impl Archive<'_> {
+    pub fn metadata(&self) -> Result<Option<Vec<u8>>, Error> {
+        self.get(METADATA_FILENAME)
+    }
+
+    pub fn set_metadata(&mut self, data: impl Into<Vec<u8>>) -> Result<(), Error> {
+        if self.contains(METADATA_FILENAME) {
+            self.replace(METADATA_FILENAME, data).map(|_| ())
+        } else {
+            self.insert(METADATA_FILENAME, data)
+        }
+    }
+}
+
+impl ArchiveBuilder {
+    pub fn add_metadata(&mut self, data: impl Into<Vec<u8>>) -> &mut Self {
+        self.add_file(METADATA_FILENAME, data)
+    }
+}
@@ -0,0 +1,77 @@
+//! Checks for names about to be written to a directory. The client looks
+//! files up by bare name, so archives are flat, and everything it ships with
+//! is a lowercase ASCII name with a short extension like "gfaydark.wld".
+//! Nothing here is applied when reading, archives in the wild break every
+//! one of these rules.
+
+use crate::Error;
+
+/// Long enough for any real name, short enough for every filesystem they
+/// might be extracted to
+pub const MAX_LENGTH: usize = 255;
+
+/// Extensions run from the classic three letters up to four, like "json"
+pub const MAX_EXTENSION_LENGTH: usize = 4;
+
+// Characters Windows won't allow in a filename, where the client runs
+const RESERVED: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Whether the name can be written as is. The null terminator is added when
+/// writing so it mustn't be part of the name.
+pub fn validate(name: &str) -> Result<(), Error> {
+    let invalid = |reason| {
+        Err(Error::InvalidFilename {
+            filename: name.to_string(),
+            reason,
+        })
+    };
+
+    if name.is_empty() {
+        return invalid("it's empty");
+    }
+    if name.len() > MAX_LENGTH {
+        return invalid("it's too long");
+    }
+    if name.contains(['/', '\\']) {
+        return invalid("archives can't contain directories");
+    }
+    if name.contains('\0') {
+        return invalid("it contains a null");
+    }
+    if !name.is_ascii() || name.contains(|c: char| c.is_ascii_control() || RESERVED.contains(&c)) {
+        return invalid("it contains a character the client can't use");
+    }
+    if name.chars().any(|c| c.is_ascii_uppercase()) {
+        return invalid("it isn't lowercase");
+    }
+    match name.rsplit_once('.') {
+        Some((stem, extension))
+            if !stem.is_empty()
+                && !extension.is_empty()
+                && extension.len() <= MAX_EXTENSION_LENGTH =>
+        {
+            Ok(())
+        }
+        _ => invalid("it needs a short extension"),
+    }
+}
+
+/// Fixes what can be fixed without guessing: surrounding whitespace and
+/// trailing nulls are trimmed, any directories are dropped and the name is
+/// lowercased. The result is then validated, so a name with no extension is
+/// still rejected.
+pub fn normalize(name: &str) -> Result<String, Error> {
+    let trimmed = name.trim_end_matches('\0').trim();
+    let bare = trimmed.rsplit(['/', '\\']).next().unwrap_or(trimmed);
+    let normalized = bare.to_ascii_lowercase();
+    validate(&normalized)
+        .map(|_| normalized)
+        .map_err(|e| match e {
+            // Report the name as it was given
+            Error::InvalidFilename { reason, .. } => Error::InvalidFilename {
+                filename: name.to_string(),
+                reason,
+            },
+            e => e,
+        })
+}
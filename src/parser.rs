@@ -1,26 +1,598 @@
-use nom::bytes::complete::take;
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::Arc;
+
+use nom::bytes::complete::{tag, take};
+use nom::combinator::opt;
 use nom::error::ErrorKind;
 use nom::multi::{count, length_data};
+#[cfg(any(feature = "textures", feature = "sounds"))]
+use nom::number::complete::le_u16;
 use nom::number::complete::le_u32;
 use nom::sequence::tuple;
 use nom::IResult;
 
-const HEADER_SIZE: u32 = 12;
-const BLOCK_HEADER_SIZE: u32 = 8;
+use crate::buffer::Buffer;
+use crate::crc::filename_crc;
+use crate::edit::EditMode;
+use crate::error::Section;
+use crate::inflate::{Inflate, Zlib};
+use crate::progress::{Progress, Tracker};
+#[cfg(feature = "fs")]
+use crate::reload::FileStamp;
+use crate::Error;
+#[cfg(feature = "tracing")]
+use tracing::{debug, warn};
+
+pub const HEADER_SIZE: u32 = 12;
+pub const BLOCK_HEADER_SIZE: u32 = 8;
+pub const ENTRY_SIZE: usize = 12;
+/// "PFS " read as a little endian integer
+pub const PFS_MAGIC: u32 = 0x2053_4650;
+pub(crate) const PFS_VERSION: u32 = 0x0002_0000;
+// Seen in archives from beta clients
+pub(crate) const PFS_VERSION_1: u32 = 0x0001_0000;
+/// Both share the same layout
+pub const SUPPORTED_VERSIONS: [u32; 2] = [PFS_VERSION_1, PFS_VERSION];
+// Unlike "PFS " this starts the file rather than following the entry table pointer
+const NPAK_MAGIC: &[u8; 4] = b"NPAK";
+pub const DIRECTORY_CRC: u32 = 0x6158_0AC9;
+pub const FOOTER_STRING: &[u8; 5] = b"STEVE";
+// "STEVE" and a u32 timestamp
+pub(crate) const FOOTER_SIZE: usize = 9;
+
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Skip entries that can't be read instead of failing the whole archive
+    pub recover: bool,
+    /// Used in place of flate2 when set
+    pub inflater: Option<Arc<dyn Inflate>>,
+    pub encoding: Encoding,
+    /// Called as each entry is read, and later as files are decompressed
+    pub progress: Option<Progress>,
+    /// Reject anything a well formed archive wouldn't contain instead of
+    /// reading around it: trailing bytes after the footer, directory names
+    /// that don't match their entry's CRC, blocks that overlap each other and
+    /// files that don't inflate to their stored size. Every file is inflated
+    /// up front and nothing is recovered.
+    pub strict: bool,
+    pub lookup: LookupOptions,
+    pub limits: Limits,
+}
+
+/// Caps on what an archive can declare, so a crafted one fails to parse
+/// rather than running the process out of memory. The defaults are well
+/// beyond anything shipped with the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_entries: u32,
+    /// Uncompressed size of any one file
+    pub max_file_size: u32,
+    /// Blocks in any one file
+    pub max_blocks: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_entries: 100_000,
+            max_file_size: 256 * 1024 * 1024,
+            max_blocks: 65_536,
+        }
+    }
+}
+
+impl Limits {
+    pub(crate) fn check_entries(&self, entries: &[Entry]) -> Result<(), Error> {
+        if entries.len() > self.max_entries as usize {
+            return Err(Error::LimitExceeded {
+                limit: "max_entries",
+                max: self.max_entries.into(),
+            });
+        }
+        if entries
+            .iter()
+            .any(|entry| entry.uncompressed_size > self.max_file_size)
+        {
+            return Err(Error::LimitExceeded {
+                limit: "max_file_size",
+                max: self.max_file_size.into(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// How filenames passed to `Archive::get` and friends are matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LookupOptions {
+    /// The client ignores case, as does the default
+    pub case_sensitive: bool,
+}
+
+/// The key filenames are matched by when case is ignored
+pub fn normalize_filename(filename: &str) -> String {
+    filename.to_ascii_lowercase()
+}
+
+/// How filenames in the directory are decoded. Names are stored as raw bytes
+/// and some older archives contain Latin-1 characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// Invalid sequences are replaced with U+FFFD
+    #[default]
+    Utf8Lossy,
+    Latin1,
+}
+
+impl Encoding {
+    pub fn decode(self, name: &[u8]) -> String {
+        match self {
+            Self::Utf8Lossy => String::from_utf8_lossy(name).into_owned(),
+            Self::Latin1 => name.iter().map(|&byte| char::from(byte)).collect(),
+        }
+    }
+}
+
+/// An entry that was skipped while recovering a damaged archive
+#[derive(Debug)]
+pub struct Failure {
+    pub filename: String,
+    pub crc: u32,
+    pub error: Error,
+}
+
+pub fn parse(data: Buffer<'_>) -> Result<Archive<'_>, Error> {
+    parse_with(data, &ParseOptions::default())
+}
+
+pub fn parse_with<'a>(data: Buffer<'a>, options: &ParseOptions) -> Result<Archive<'a>, Error> {
+    let inflater = options.inflater.clone().unwrap_or_else(|| Arc::new(Zlib));
+    check_format(&data)?;
+    let (remaining, (header, entry_count, mut entries, footer)) = archive(&data)?;
+    if options.strict && !remaining.is_empty() {
+        return Err(Error::TrailingData {
+            offset: data.len() - remaining.len(),
+        });
+    }
+    if sniff(remaining) {
+        warn!(
+            "another archive follows at {:#x}, read it with load_all",
+            data.len() - remaining.len()
+        );
+    }
+    debug!(
+        "{} entries, entry table at {:#x}",
+        entries.len(),
+        header.pointer
+    );
+    options.limits.check_entries(&entries)?;
+    let recover = options.recover && !options.strict;
+    let mut errors = Vec::with_capacity(entries.len());
+    let mut tracker = Tracker::new(options.progress, Some(entries.len()));
+    for entry in entries.iter_mut() {
+        #[cfg(feature = "tracing")]
+        let _entry =
+            tracing::debug_span!("entry", crc = entry.filename_crc, pointer = entry.pointer)
+                .entered();
+        match blocks(&data, entry, options.limits.max_blocks) {
+            Ok(blocks) => {
+                debug!("{} blocks, {} bytes", blocks.len(), entry.uncompressed_size);
+                entry.blocks = blocks;
+                errors.push(None);
+            }
+            Err(e) if recover => {
+                warn!("entry {:#010x} is unreadable: {}", entry.filename_crc, e);
+                errors.push(Some(e));
+            }
+            Err(e) => return Err(e),
+        }
+        tracker.advance(0);
+    }
+    if options.strict {
+        check_overlaps(&header, &entries)?;
+    }
+    let mut failures = Vec::new();
+
+    // The directory is usually the last entry but it's only reliably
+    // identifiable by its CRC, some archives don't have one at all
+    let mut directory_entry = None;
+    let mut directory_error = None;
+    for position in directory_positions(&entries).into_iter().rev() {
+        directory_entry = Some(entries.remove(position));
+        directory_error = errors.remove(position);
+    }
+    let listed = match (&directory_entry, directory_error) {
+        (Some(entry), None) => {
+            match entry
+                .decompress(&data, &*inflater)
+                .and_then(|names| read_directory(&names))
+            {
+                Ok(names) => names,
+                Err(e) => {
+                    // Whatever names come before the damage are kept, the
+                    // entries left over are named after their CRC
+                    let (names, error) = salvage_directory(entry, &data, &*inflater);
+                    let error = error.unwrap_or(e);
+                    if !recover {
+                        return Err(error);
+                    }
+                    warn!(
+                        "salvaged {} names from the directory: {}",
+                        names.len(),
+                        error
+                    );
+                    failures.push(Failure {
+                        filename: unnamed(DIRECTORY_CRC),
+                        crc: DIRECTORY_CRC,
+                        error,
+                    });
+                    names
+                }
+            }
+        }
+        (Some(_), Some(e)) => {
+            failures.push(Failure {
+                filename: unnamed(DIRECTORY_CRC),
+                crc: DIRECTORY_CRC,
+                error: e,
+            });
+            Vec::new()
+        }
+        (None, _) => {
+            debug!("no directory, every entry is unnamed");
+            Vec::new()
+        }
+    };
+
+    let mut filenames = Vec::with_capacity(entries.len());
+    let mut raw_filenames = Vec::with_capacity(entries.len());
+    let mut readable = Vec::with_capacity(entries.len());
+    if options.strict && directory_entry.is_some() && listed.len() != entries.len() {
+        return Err(Error::DirectoryMismatch {
+            entries: entries.len(),
+            names: listed.len(),
+        });
+    }
+    let names = associate(&entries, listed);
+    for ((mut entry, error), raw_filename) in entries.into_iter().zip(errors).zip(names) {
+        if options.strict {
+            if let Some(name) = &raw_filename {
+                check_crc(&entry, name, options.encoding)?;
+            }
+            entry.decompress(&data, &*inflater)?;
+        }
+        let raw_filename = raw_filename.unwrap_or_else(|| unnamed(entry.filename_crc).into_bytes());
+        let filename = options.encoding.decode(&raw_filename);
+        // Recovering means finding out up front which entries won't inflate
+        let error = match error {
+            Some(e) => Some(e),
+            None if recover => entry.decompress(&data, &*inflater).err(),
+            None => None,
+        };
+        match error {
+            Some(error) => {
+                warn!("skipping {}: {}", filename, error);
+                failures.push(Failure {
+                    filename,
+                    crc: entry.filename_crc,
+                    error,
+                });
+            }
+            None => {
+                entry.filename = filename.clone();
+                filenames.push(filename);
+                raw_filenames.push(raw_filename);
+                readable.push(entry);
+            }
+        }
+    }
+    let index = index(&filenames);
+
+    Ok(Archive {
+        data,
+        header,
+        entry_count,
+        entries: readable,
+        directory: directory_entry,
+        footer,
+        filenames,
+        raw_filenames,
+        index,
+        failures,
+        inflater,
+        encoding: options.encoding,
+        edit_mode: EditMode::default(),
+        progress: options.progress,
+        lookup: options.lookup,
+        #[cfg(feature = "fs")]
+        stamp: None,
+    })
+}
+
+// Anything that isn't a PFS archive would otherwise fail somewhere deep in
+// the entry table, or be misread if its layout has changed
+pub(crate) fn check_format(data: &[u8]) -> Result<(), Error> {
+    if let Some(format) = Format::detect(data).filter(|&format| format != Format::Pfs) {
+        return Err(Error::UnsupportedFormat(format));
+    }
+    if let Ok((_, header)) = header(data) {
+        if header.magic_number != PFS_MAGIC {
+            return Err(Error::NotAPfsArchive {
+                found_magic: header.magic_number,
+            });
+        }
+        if !SUPPORTED_VERSIONS.contains(&header.version) {
+            return Err(Error::UnsupportedVersion {
+                version: header.version,
+            });
+        }
+    }
+    Ok(())
+}
+
+// Block data has to sit between the header and the entry table, and entries
+// may only share data when they're identical, like the writer's deduplicated
+// files
+pub(crate) fn check_overlaps<'e>(
+    header: &Header,
+    entries: impl IntoIterator<Item = &'e Entry>,
+) -> Result<(), Error> {
+    let mut spans: Vec<(usize, usize)> = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let last = entry.blocks.last()?;
+            let start = entry.pointer as usize;
+            Some((start, last.offset + last.compressed_size as usize))
+        })
+        .collect();
+    spans.sort_unstable();
+    spans.dedup();
+
+    let mut end = HEADER_SIZE as usize;
+    for &(start, span_end) in &spans {
+        if start < end {
+            return Err(Error::OverlappingBlocks { offset: start });
+        }
+        end = span_end;
+    }
+    if end > header.pointer as usize {
+        return Err(Error::OverlappingBlocks {
+            offset: header.pointer as usize,
+        });
+    }
+    Ok(())
+}
+
+fn check_crc(entry: &Entry, name: &[u8], encoding: Encoding) -> Result<(), Error> {
+    let actual = filename_crc(name);
+    if actual != entry.filename_crc {
+        return Err(Error::CrcMismatch {
+            filename: encoding.decode(name),
+            expected: entry.filename_crc,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+// Length of the archive at the start of `data`, up to the end of its footer
+pub(crate) fn archive_len(data: &[u8]) -> Result<usize, Error> {
+    check_format(data)?;
+    let (remaining, _) = archive(data)?;
+    Ok(data.len() - remaining.len())
+}
+
+// Pairs each entry with its name from the directory. Names are matched by
+// CRC first, since some packers don't list names in the same order as the
+// data. Whatever is left over is paired up in order, which covers entries
+// with a bad CRC in archives that are otherwise laid out as expected.
+pub(crate) fn associate(entries: &[Entry], listed: Vec<Vec<u8>>) -> Vec<Option<Vec<u8>>> {
+    let mut by_crc: HashMap<u32, VecDeque<usize>> = HashMap::with_capacity(listed.len());
+    for (position, name) in listed.iter().enumerate() {
+        by_crc
+            .entry(filename_crc(name))
+            .or_default()
+            .push_back(position);
+    }
+
+    let mut listed: Vec<_> = listed.into_iter().map(Some).collect();
+    let mut names: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            by_crc
+                .get_mut(&entry.filename_crc)
+                .and_then(VecDeque::pop_front)
+                .and_then(|position| listed[position].take())
+        })
+        .collect();
+
+    let mut leftover = listed.into_iter().flatten();
+    for name in names.iter_mut().filter(|name| name.is_none()) {
+        *name = leftover.next();
+        if let Some(name) = name {
+            warn!(
+                "no entry has the CRC of {}, matched by position instead",
+                String::from_utf8_lossy(name)
+            );
+        }
+    }
+    names
+}
+
+// Every entry with the directory's CRC, which is never a file. The first is
+// read as the directory, others are left behind by patchers that append a
+// new one without removing the old entry.
+pub(crate) fn directory_positions(entries: &[Entry]) -> Vec<usize> {
+    let positions: Vec<_> = (0..entries.len())
+        .filter(|&position| entries[position].filename_crc == DIRECTORY_CRC)
+        .collect();
+    if positions.len() > 1 {
+        warn!("{} directories, only the first is read", positions.len());
+    }
+    positions
+}
+
+// Reads as many names as it can from a damaged directory, inflating blocks
+// until one fails and then names until one doesn't fit, along with the error
+// that stopped it
+fn salvage_directory(
+    entry: &Entry,
+    data: &[u8],
+    inflater: &dyn Inflate,
+) -> (Vec<Vec<u8>>, Option<Error>) {
+    let mut inflated = Vec::new();
+    let mut error = None;
+    for block in &entry.blocks {
+        if let Err(source) = block.inflate(data, inflater, &mut inflated) {
+            error = Some(Error::Decompress {
+                offset: block.offset,
+                source,
+            });
+            break;
+        }
+    }
+
+    let mut names = Vec::new();
+    let mut input = inflated.as_slice();
+    let count = match le_u32::<(&[u8], ErrorKind)>(input) {
+        Ok((remaining, count)) => {
+            input = remaining;
+            Some(count)
+        }
+        Err(_) => None,
+    };
+    for _ in 0..count.unwrap_or(0) {
+        match directory_string(input) {
+            Ok((remaining, name)) => {
+                names.push(name);
+                input = remaining;
+            }
+            Err(_) => break,
+        }
+    }
+    if count.is_none_or(|count| names.len() < count as usize) && error.is_none() {
+        error = Some(Error::CorruptDirectory {
+            offset: inflated.len() - input.len(),
+            names: names.len(),
+        });
+    }
+    (names, error)
+}
+
+// Name given to entries that aren't listed in the directory
+pub(crate) fn unnamed(crc: u32) -> String {
+    format!("crc_0x{:08X}.bin", crc)
+}
+
+// Lookups are case insensitive so filenames are keyed in lowercase
+pub(crate) fn index(filenames: &[String]) -> HashMap<String, usize> {
+    let mut index = HashMap::with_capacity(filenames.len());
+    for (position, filename) in filenames.iter().enumerate() {
+        index
+            .entry(normalize_filename(filename))
+            .or_insert(position);
+    }
+    index
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    V1,
+    V2,
+    Unknown(u32),
+}
+
+impl From<u32> for Version {
+    fn from(version: u32) -> Self {
+        match version {
+            PFS_VERSION_1 => Self::V1,
+            PFS_VERSION => Self::V2,
+            version => Self::Unknown(version),
+        }
+    }
+}
+
+impl From<Version> for u32 {
+    fn from(version: Version) -> Self {
+        match version {
+            Version::V1 => PFS_VERSION_1,
+            Version::V2 => PFS_VERSION,
+            Version::Unknown(version) => version,
+        }
+    }
+}
+
+/// Containers the parser can tell apart by their header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// .s3d, .eqg and .pfs archives
+    Pfs,
+    /// NPAK containers shipped as .pak by Trilogy era clients. These are
+    /// recognised so they can be reported, but their layout isn't known well
+    /// enough to read them yet.
+    T3d,
+}
+
+impl Format {
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(NPAK_MAGIC) {
+            Some(Self::T3d)
+        } else if sniff(data) {
+            Some(Self::Pfs)
+        } else {
+            None
+        }
+    }
+}
+
+/// Only the header is read, so this is cheap enough to run on every file in a directory
+pub fn sniff(data: &[u8]) -> bool {
+    magic(data) == Some(PFS_MAGIC)
+}
+
+fn magic(data: &[u8]) -> Option<u32> {
+    header(data).ok().map(|(_, header)| header.magic_number)
+}
 
-pub fn parse(data: &[u8]) -> Result<Archive, nom::Err<(&[u8], ErrorKind)>> {
-    let (_, archive) = archive(&data[..])?;
-    Ok(archive)
+// Reads a field out of a file stored in the archive, `None` if it's too short
+#[cfg(any(feature = "textures", feature = "sounds"))]
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    le_u32::<(&[u8], ErrorKind)>(data.get(offset..)?)
+        .ok()
+        .map(|(_, value)| value)
+}
+
+#[cfg(any(feature = "textures", feature = "sounds"))]
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    le_u16::<(&[u8], ErrorKind)>(data.get(offset..)?)
+        .ok()
+        .map(|(_, value)| value)
 }
 
 #[derive(Debug)]
 pub struct Header {
-    pointer: u32,
+    pub(crate) pointer: u32,
     magic_number: u32,
-    version: u32,
+    pub(crate) version: u32,
+}
+
+impl Header {
+    /// Offset of the entry table, which follows the block data
+    pub fn pointer(&self) -> u32 {
+        self.pointer
+    }
+
+    pub fn magic_number(&self) -> u32 {
+        self.magic_number
+    }
+
+    pub fn version(&self) -> Version {
+        Version::from(self.version)
+    }
 }
 
-fn header(input: &[u8]) -> IResult<&[u8], Header> {
+pub fn header(input: &[u8]) -> IResult<&[u8], Header> {
     let (remaining, (pointer, magic_number, version)) = tuple((le_u32, le_u32, le_u32))(input)?;
     Ok((
         remaining,
@@ -32,124 +604,265 @@ fn header(input: &[u8]) -> IResult<&[u8], Header> {
     ))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Entry {
-    filename_crc: u32,
-    pointer: u32,
-    pub uncompressed_size: u32,
-    pub blocks: Option<Vec<Block>>,
+    // Resolved from the directory once the whole archive has been read, empty
+    // for the directory itself
+    pub(crate) filename: String,
+    pub(crate) filename_crc: u32,
+    pub(crate) pointer: u32,
+    pub(crate) uncompressed_size: u32,
+    pub(crate) blocks: Vec<Block>,
 }
 
-fn entry(input: &[u8]) -> IResult<&[u8], Entry> {
+pub fn entry(input: &[u8]) -> IResult<&[u8], Entry> {
     let (remaining, (filename_crc, pointer, uncompressed_size)) =
         tuple((le_u32, le_u32, le_u32))(input)?;
     Ok((
         remaining,
         Entry {
+            filename: String::new(),
             filename_crc,
             pointer,
             uncompressed_size,
-            blocks: None,
+            blocks: Vec::new(),
         },
     ))
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Block {
     pub compressed_size: u32,
     pub uncompressed_size: u32,
-    pub data: Vec<u8>,
+    // Offset of the compressed data from the start of the archive
+    pub(crate) offset: usize,
+}
+
+impl Block {
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub(crate) fn data<'a>(&self, archive: &'a [u8]) -> &'a [u8] {
+        &archive[self.offset..self.offset + self.compressed_size as usize]
+    }
+
+    /// Some community packers store blocks raw when deflating wouldn't shrink
+    /// them. Their sizes match and they don't start with a zlib header.
+    pub fn is_stored(&self, archive: &[u8]) -> bool {
+        self.stored(self.data(archive))
+    }
+
+    fn stored(&self, data: &[u8]) -> bool {
+        self.compressed_size == self.uncompressed_size && !is_zlib(data)
+    }
+
+    pub(crate) fn inflate(
+        &self,
+        archive: &[u8],
+        inflater: &dyn Inflate,
+        out: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        self.inflate_data(self.data(archive), inflater, out)
+    }
+
+    // Takes the block's compressed data rather than the whole archive
+    pub(crate) fn inflate_data(
+        &self,
+        data: &[u8],
+        inflater: &dyn Inflate,
+        out: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        if self.stored(data) {
+            out.extend_from_slice(data);
+            Ok(())
+        } else {
+            inflater.inflate(data, self.uncompressed_size as usize, out)
+        }
+    }
+}
+
+// Deflate with a 32K window, which is all zlib writes, and a valid header checksum
+pub(crate) fn is_zlib(data: &[u8]) -> bool {
+    match data {
+        [0x78, flags, ..] => u16::from_be_bytes([0x78, *flags]).is_multiple_of(31),
+        _ => false,
+    }
 }
 
-pub fn block(input: &[u8]) -> IResult<&[u8], Block> {
-    let (i, (compressed_size, uncompressed_size)) = tuple((le_u32, le_u32))(input)?;
-    let (remaining, data) = take(compressed_size)(i)?;
+pub fn block(input: &[u8], offset: usize) -> IResult<&[u8], Block> {
+    let i = input
+        .get(offset..)
+        .ok_or(nom::Err::Error((input, ErrorKind::Eof)))?;
+    let (i, (compressed_size, uncompressed_size)) = tuple((le_u32, le_u32))(i)?;
+    let (remaining, _) = take(compressed_size)(i)?;
     Ok((
         remaining,
         Block {
             compressed_size,
             uncompressed_size,
-            data: Vec::from(data),
+            // Can't overflow, the block header was just read from the input at this offset
+            offset: offset + BLOCK_HEADER_SIZE as usize,
         },
     ))
 }
 
 #[derive(Debug)]
 pub struct Footer {
-    footer_string: Vec<u8>,
-    timestamp: u32,
+    pub(crate) timestamp: u32,
 }
 
-fn footer(input: &[u8]) -> IResult<&[u8], Footer> {
-    let (remaining, (footer_string, timestamp)) = tuple((take(5usize), le_u32))(input)?;
-    Ok((
-        remaining,
-        Footer {
-            footer_string: Vec::from(footer_string),
-            timestamp,
-        },
-    ))
+impl Footer {
+    /// Seconds since the Unix epoch
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+}
+
+pub fn footer(input: &[u8]) -> IResult<&[u8], Footer> {
+    let (remaining, (_, timestamp)) = tuple((tag(FOOTER_STRING), le_u32))(input)?;
+    Ok((remaining, Footer { timestamp }))
 }
 
 #[derive(Debug)]
-pub struct Archive {
-    header: Header,
-    entry_count: u32,
+pub struct Archive<'a> {
+    pub(crate) data: Buffer<'a>,
+    pub(crate) header: Header,
+    pub(crate) entry_count: u32,
     pub entries: Vec<Entry>,
-    footer: Footer,
+    pub(crate) directory: Option<Entry>,
+    // .eqg archives are not always terminated with a footer
+    pub(crate) footer: Option<Footer>,
+    pub(crate) filenames: Vec<String>,
+    // Filenames as stored in the directory, before decoding
+    pub(crate) raw_filenames: Vec<Vec<u8>>,
+    pub(crate) index: HashMap<String, usize>,
+    pub(crate) failures: Vec<Failure>,
+    pub(crate) inflater: Arc<dyn Inflate>,
+    pub(crate) encoding: Encoding,
+    pub(crate) edit_mode: EditMode,
+    pub(crate) progress: Option<Progress>,
+    pub(crate) lookup: LookupOptions,
+    // The file the archive was read from as it was then, for `reload_if_changed`
+    #[cfg(feature = "fs")]
+    pub(crate) stamp: Option<FileStamp>,
+}
+
+impl<'a> Archive<'a> {
+    /// Copies borrowed archive data so the archive can outlive its input
+    pub fn into_owned(self) -> Archive<'static> {
+        Archive {
+            data: self.data.into_owned(),
+            header: self.header,
+            entry_count: self.entry_count,
+            entries: self.entries,
+            directory: self.directory,
+            footer: self.footer,
+            filenames: self.filenames,
+            raw_filenames: self.raw_filenames,
+            index: self.index,
+            failures: self.failures,
+            inflater: self.inflater,
+            encoding: self.encoding,
+            edit_mode: self.edit_mode,
+            progress: self.progress,
+            lookup: self.lookup,
+            #[cfg(feature = "fs")]
+            stamp: self.stamp,
+        }
+    }
+}
+
+// Everything in the archive except for the block data
+type Index = (Header, u32, Vec<Entry>, Option<Footer>);
+/// Everything after the block data
+pub type EntryTable = (u32, Vec<Entry>, Option<Footer>);
+
+fn archive(input: &[u8]) -> Result<(&[u8], Index), Error> {
+    let (_, header) = header(input).map_err(|e| Error::parser(Section::Header, input, 0, e))?;
+    // A pointer into the header or past the end is reported at the pointer
+    let start = header.pointer as usize;
+    let i = match input.get(start..) {
+        Some(i) if header.pointer >= HEADER_SIZE => i,
+        _ => {
+            let e = nom::Err::Error((input, ErrorKind::Verify));
+            return Err(Error::parser(Section::Header, input, 0, e));
+        }
+    };
+    let (remaining, (entry_count, entries, footer)) =
+        entry_table(i).map_err(|e| Error::parser(Section::EntryTable, i, start, e))?;
+    Ok((remaining, (header, entry_count, entries, footer)))
 }
 
-fn archive(input: &[u8]) -> IResult<&[u8], Archive> {
-    let (i, header) = header(input)?;
-    let (i, (data, entry_count)) = tuple((take(header.pointer - HEADER_SIZE), le_u32))(i)?;
+/// The entries come back sorted by pointer, which is the order of their data
+pub fn entry_table(input: &[u8]) -> IResult<&[u8], EntryTable> {
+    let (i, entry_count) = le_u32(input)?;
+    check_count(i, entry_count, ENTRY_SIZE)?;
     let (remaining, (mut entries, footer)) =
-        tuple((count(entry, entry_count as usize), footer))(i)?;
+        tuple((count(entry, entry_count as usize), opt(footer)))(i)?;
 
     entries.sort_by_key(|a| a.pointer);
 
-    let entries: Vec<Entry> = entries
-        .into_iter()
-        .map(|mut e| {
-            let mut offset = (e.pointer - HEADER_SIZE) as usize;
-            let mut bytes_remaining = e.uncompressed_size;
-            let mut blocks = Vec::new();
-
-            while bytes_remaining > 0 {
-                let b = block(&data[offset..]).expect("Error parsing block").1;
-                offset += (BLOCK_HEADER_SIZE + b.compressed_size) as usize;
-                bytes_remaining -= b.uncompressed_size;
-                blocks.push(b);
-            }
-            e.blocks = Some(blocks);
-            e
-        })
-        .collect();
+    Ok((remaining, (entry_count, entries, footer)))
+}
 
-    Ok((
-        remaining,
-        Archive {
-            header,
-            entry_count,
-            entries,
-            footer,
-        },
-    ))
+// Stops a corrupt count from preallocating far more than the input could hold
+fn check_count(input: &[u8], count: u32, size: usize) -> Result<(), nom::Err<(&[u8], ErrorKind)>> {
+    match (count as usize).checked_mul(size) {
+        Some(needed) if needed <= input.len() => Ok(()),
+        _ => Err(nom::Err::Error((input, ErrorKind::Count))),
+    }
 }
 
-fn directory_string(input: &[u8]) -> IResult<&[u8], String> {
+pub(crate) fn blocks(input: &[u8], entry: &Entry, max_blocks: usize) -> Result<Vec<Block>, Error> {
+    let mut offset = entry.pointer as usize;
+    let mut bytes_remaining = entry.uncompressed_size;
+    let mut blocks = Vec::new();
+
+    // Empty files have no blocks, so their pointer is never followed. Some
+    // packers still write an empty block for them, which is left unread.
+    while bytes_remaining > 0 {
+        if blocks.len() == max_blocks {
+            return Err(Error::LimitExceeded {
+                limit: "max_blocks",
+                max: max_blocks as u64,
+            });
+        }
+        let (_, b) = block(input, offset).map_err(|_| Error::TruncatedBlock { offset })?;
+        // An empty block would never finish the entry and one that overshoots
+        // means the sizes in the archive can't be trusted
+        bytes_remaining = match bytes_remaining.checked_sub(b.uncompressed_size) {
+            Some(remaining) if b.uncompressed_size > 0 => remaining,
+            _ => return Err(Error::CorruptBlock { offset }),
+        };
+        offset = b.offset + b.compressed_size as usize;
+        blocks.push(b);
+    }
+    Ok(blocks)
+}
+
+fn directory_string(input: &[u8]) -> IResult<&[u8], Vec<u8>> {
     let (remaining, data) = length_data(le_u32)(input)?;
-    Ok((
-        remaining,
-        String::from_utf8(Vec::from(data))
-            .unwrap()
-            // Strings stored in directory are null terminated
-            .trim_end_matches('\0')
-            .to_string(),
-    ))
+    // Strings stored in directory are null terminated
+    let end = data
+        .iter()
+        .rposition(|&byte| byte != 0)
+        .map_or(0, |last| last + 1);
+    Ok((remaining, data[..end].to_vec()))
+}
+
+// The names in an inflated directory
+pub(crate) fn read_directory(input: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+    match directory(input) {
+        Ok((_, names)) => Ok(names),
+        Err(e) => Err(Error::parser(Section::Directory, input, 0, e)),
+    }
 }
 
-pub fn directory(input: &[u8]) -> IResult<&[u8], Vec<String>> {
+pub fn directory(input: &[u8]) -> IResult<&[u8], Vec<Vec<u8>>> {
     let (i, file_count) = le_u32(input)?;
+    // Each string is at least its length prefix
+    check_count(i, file_count, 4)?;
     let (remaining, filenames) = count(directory_string, file_count as usize)(i)?;
     Ok((remaining, filenames.to_vec()))
 }
@@ -0,0 +1,38 @@
+/// Reported after each entry is read, extracted or written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgressEvent {
+    pub entries_processed: usize,
+    /// Not known up front when streaming with `ArchiveWriter`
+    pub entry_count: Option<usize>,
+    /// Decompressed when reading, the uncompressed input when writing
+    pub uncompressed_bytes: u64,
+}
+
+pub type Progress = fn(ProgressEvent);
+
+// Keeps the running totals for a single operation
+pub(crate) struct Tracker {
+    progress: Option<Progress>,
+    event: ProgressEvent,
+}
+
+impl Tracker {
+    pub(crate) fn new(progress: Option<Progress>, entry_count: Option<usize>) -> Self {
+        Self {
+            progress,
+            event: ProgressEvent {
+                entries_processed: 0,
+                entry_count,
+                uncompressed_bytes: 0,
+            },
+        }
+    }
+
+    pub(crate) fn advance(&mut self, uncompressed_bytes: u64) {
+        if let Some(progress) = self.progress {
+            self.event.entries_processed += 1;
+            self.event.uncompressed_bytes += uncompressed_bytes;
+            progress(self.event);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! The nom parsers used to read archives, for tools that need to look at the
+//! layout on disk rather than the files in it. Every integer is a little
+//! endian u32.
+//!
+//! ```text
+//!   0                header          entry table pointer, "PFS " magic, version
+//!   HEADER_SIZE      block data      per block its compressed size, its
+//!                                    uncompressed size and the zlib stream
+//!   header.pointer   entry table     entry count, then per entry its filename
+//!                                    CRC, pointer to its first block and
+//!                                    uncompressed size
+//!   after the table  footer          "STEVE" and a timestamp, missing from
+//!                                    some .eqg archives
+//! ```
+//!
+//! Filenames are stored in the directory, an entry with the CRC
+//! `DIRECTORY_CRC`. Inflated it's a count, then per file a length prefixed and
+//! null terminated name.
+//!
+//! Parsers take the bytes starting at what they read and return whatever
+//! follows, except for `block` which takes the whole archive and an offset so
+//! it can record where the block's data is.
+
+pub use nom::IResult;
+
+pub use crate::parser::{
+    block, directory, entry, entry_table, footer, header, Block, Entry, EntryTable, Footer, Header,
+    BLOCK_HEADER_SIZE, DIRECTORY_CRC, ENTRY_SIZE, FOOTER_STRING, HEADER_SIZE, PFS_MAGIC,
+};
@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::slice;
+
+use crate::inflate::Inflate;
+use crate::parser::Block;
+
+/// Inflates an entry one block at a time as it is read
+pub struct EntryReader<'a> {
+    archive: &'a [u8],
+    blocks: slice::Iter<'a, Block>,
+    inflater: &'a dyn Inflate,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<'a> EntryReader<'a> {
+    pub(crate) fn new(archive: &'a [u8], blocks: &'a [Block], inflater: &'a dyn Inflate) -> Self {
+        Self {
+            archive,
+            blocks: blocks.iter(),
+            inflater,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<'a> Read for EntryReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.position == self.buffer.len() {
+            match self.blocks.next() {
+                Some(block) => {
+                    self.buffer.clear();
+                    self.position = 0;
+                    block.inflate(self.archive, self.inflater, &mut self.buffer)?;
+                    check_size(block, self.buffer.len())?;
+                }
+                None => return Ok(0),
+            }
+        }
+        let read = buf.len().min(self.buffer.len() - self.position);
+        buf[..read].copy_from_slice(&self.buffer[self.position..self.position + read]);
+        self.position += read;
+        Ok(read)
+    }
+}
+
+// Decompressed blocks kept around by an `EntryFile`
+const CACHED_BLOCKS: usize = 4;
+
+/// Seekable view of an entry, blocks are inflated as they're needed and the
+/// most recently used few are kept so nearby reads don't inflate them again
+pub struct EntryFile<'a> {
+    archive: &'a [u8],
+    blocks: &'a [Block],
+    inflater: &'a dyn Inflate,
+    // Uncompressed offset of the start of each block
+    starts: Vec<u64>,
+    size: u64,
+    position: u64,
+    cache: VecDeque<(usize, Vec<u8>)>,
+}
+
+impl<'a> EntryFile<'a> {
+    pub(crate) fn new(archive: &'a [u8], blocks: &'a [Block], inflater: &'a dyn Inflate) -> Self {
+        let mut size = 0;
+        let starts = blocks
+            .iter()
+            .map(|block| {
+                let start = size;
+                size += u64::from(block.uncompressed_size);
+                start
+            })
+            .collect();
+        Self {
+            archive,
+            blocks,
+            inflater,
+            starts,
+            size,
+            position: 0,
+            cache: VecDeque::with_capacity(CACHED_BLOCKS),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn block(&mut self, index: usize) -> io::Result<&[u8]> {
+        match self.cache.iter().position(|(cached, _)| *cached == index) {
+            Some(position) => {
+                let block = self.cache.remove(position).expect("cached block");
+                self.cache.push_front(block);
+            }
+            None => {
+                let mut data = Vec::new();
+                let block = &self.blocks[index];
+                block.inflate(self.archive, self.inflater, &mut data)?;
+                check_size(block, data.len())?;
+                if self.cache.len() == CACHED_BLOCKS {
+                    self.cache.pop_back();
+                }
+                self.cache.push_front((index, data));
+            }
+        }
+        Ok(&self.cache[0].1)
+    }
+}
+
+impl<'a> Read for EntryFile<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.position >= self.size {
+            return Ok(0);
+        }
+        let index = self.starts.partition_point(|&start| start <= self.position) - 1;
+        let offset = (self.position - self.starts[index]) as usize;
+        let block = self.block(index)?;
+        let read = buf.len().min(block.len() - offset);
+        buf[..read].copy_from_slice(&block[offset..offset + read]);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl<'a> Seek for EntryFile<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
+
+// A block that inflates to anything but its stored size is corrupt, passing
+// it on would hand the caller silently damaged data
+fn check_size(block: &Block, inflated: usize) -> io::Result<()> {
+    if inflated == block.uncompressed_size as usize {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "block inflated to {} bytes but its stored size is {}",
+                inflated, block.uncompressed_size
+            ),
+        ))
+    }
+}
@@ -0,0 +1,140 @@
+use std::convert::TryInto;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::parser::{ENTRY_SIZE, FOOTER_SIZE, FOOTER_STRING};
+use crate::writer::{footer_timestamp, to_u32, validate, write_blocks};
+use crate::{Archive, Error, WriteOptions};
+
+// What's checked to tell whether a file has changed since it was read,
+// without reading it again. The tail holds the footer and its timestamp
+// when there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+    tail: Vec<u8>,
+}
+
+impl FileStamp {
+    pub(crate) fn read(file: &mut File) -> io::Result<Self> {
+        let metadata = file.metadata()?;
+        let len = metadata.len();
+        let mut tail = Vec::with_capacity(FOOTER_SIZE);
+        file.seek(SeekFrom::Start(len.saturating_sub(FOOTER_SIZE as u64)))?;
+        file.take(FOOTER_SIZE as u64).read_to_end(&mut tail)?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(Self {
+            len,
+            modified: metadata.modified().ok(),
+            tail,
+        })
+    }
+}
+
+impl Archive<'_> {
+    /// Parses the archive at `path` again if it's changed since this archive
+    /// was read from it, returning whether it had. The file's size,
+    /// modification time and footer are checked first, it's only read in
+    /// full when they differ or this archive wasn't read from a file.
+    pub fn reload_if_changed(&mut self, path: impl AsRef<Path>) -> Result<bool, Error> {
+        let mut file = File::open(path)?;
+        let stamp = FileStamp::read(&mut file)?;
+        if self.stamp.as_ref() == Some(&stamp) {
+            return Ok(false);
+        }
+        let mut buffer = Vec::with_capacity(stamp.len as usize);
+        file.read_to_end(&mut buffer)?;
+        let changed = buffer[..] != self.data[..];
+        if changed {
+            self.reparse(buffer)?;
+        }
+        self.stamp = Some(stamp);
+        Ok(changed)
+    }
+
+    /// Replaces a file in the archive at `path`, which this archive was read
+    /// from. When the new blocks, compressed with `options`, fit where the old
+    /// ones were only they, the file's size in the entry table and the footer
+    /// timestamp are written, otherwise the whole archive is rebuilt and
+    /// saved. Returns whether the file was patched in place. Files sharing
+    /// their blocks with another are always rebuilt.
+    pub fn patch_in_place(
+        &mut self,
+        path: impl AsRef<Path>,
+        filename: &str,
+        data: impl Into<Vec<u8>>,
+        options: &WriteOptions,
+    ) -> Result<bool, Error> {
+        validate(options)?;
+        let path = path.as_ref();
+        let data = data.into();
+        self.reload_if_changed(path)?;
+        let position = self
+            .index_of(filename)
+            .ok_or_else(|| Error::FileNotFound(filename.to_string()))?;
+
+        let entry = &self.entries[position];
+        let shared = self
+            .entries
+            .iter()
+            .filter(|other| other.pointer == entry.pointer && !other.blocks.is_empty())
+            .count()
+            > 1;
+        // Checked before anything is written, a failure halfway would leave
+        // the file corrupt
+        let size = to_u32(data.len())?;
+        let mut blocks = Vec::new();
+        write_blocks(&mut blocks, &data, options)?;
+        let record = self.entry_record(entry.filename_crc, entry.pointer);
+
+        match record {
+            Some(record) if !shared && blocks.len() <= self.block_bytes(entry).len() => {
+                let pointer = entry.pointer as usize;
+                // The uncompressed size follows the CRC and pointer
+                let mut patches =
+                    vec![(pointer, blocks), (record + 8, size.to_le_bytes().to_vec())];
+                if self.footer.is_some() {
+                    let table = self.header.pointer as usize + 4;
+                    let footer = table + self.entry_count as usize * ENTRY_SIZE;
+                    let timestamp = footer_timestamp(SystemTime::now()).to_le_bytes();
+                    patches.push((footer + FOOTER_STRING.len(), timestamp.to_vec()));
+                }
+
+                let mut patched = self.data.to_vec();
+                let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+                for (offset, bytes) in &patches {
+                    patched[*offset..*offset + bytes.len()].copy_from_slice(bytes);
+                    file.seek(SeekFrom::Start(*offset as u64))?;
+                    file.write_all(bytes)?;
+                }
+                self.reparse(patched)?;
+                self.stamp = Some(FileStamp::read(&mut file)?);
+                Ok(true)
+            }
+            _ => {
+                self.replace(filename, data)?;
+                self.save(path)?;
+                Ok(false)
+            }
+        }
+    }
+
+    // Offset of an entry in the entry table, which isn't in the same order
+    // as `entries`
+    fn entry_record(&self, crc: u32, pointer: u32) -> Option<usize> {
+        let table = self.header.pointer as usize;
+        let count = u32::from_le_bytes(self.data.get(table..table + 4)?.try_into().ok()?);
+        let records = self
+            .data
+            .get(table + 4..table + 4 + count as usize * ENTRY_SIZE)?;
+        records
+            .chunks_exact(ENTRY_SIZE)
+            .position(|record| {
+                record[..4] == crc.to_le_bytes() && record[4..8] == pointer.to_le_bytes()
+            })
+            .map(|position| table + 4 + position * ENTRY_SIZE)
+    }
+}
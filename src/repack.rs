@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use crate::parser::{Entry, DIRECTORY_CRC, HEADER_SIZE};
+use crate::progress::Tracker;
+use crate::writer::{to_u32, validate, write_blocks, write_header, write_index};
+use crate::{Archive, Error, WriteOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepackReport {
+    /// Size of the whole archive
+    pub before: u64,
+    pub after: u64,
+    pub recompressed: usize,
+    /// Entries that didn't get any smaller, usually already compressed
+    /// textures, and kept their original blocks
+    pub kept: usize,
+}
+
+impl RepackReport {
+    /// Never negative, blocks that don't shrink are kept and no footer is added
+    pub fn saved(&self) -> u64 {
+        self.before.saturating_sub(self.after)
+    }
+}
+
+impl Archive<'_> {
+    /// Compresses every entry again with `options`, keeping the new blocks only
+    /// where they're smaller. Entries stay in the same order, files that share
+    /// blocks keep sharing them and the footer timestamp is kept. A footer is
+    /// never added, and dropped if `options.footer` is off.
+    pub fn repack(&mut self, options: &WriteOptions) -> Result<RepackReport, Error> {
+        validate(options)?;
+        let mut tracker = Tracker::new(options.progress, Some(self.entries.len()));
+        let mut report = RepackReport {
+            before: self.data.len() as u64,
+            after: 0,
+            recompressed: 0,
+            kept: 0,
+        };
+
+        let mut data = Vec::new();
+        let mut index = Vec::with_capacity(self.entries.len() + 1);
+        let mut written: HashMap<u32, u32> = HashMap::new();
+        for entry in self.entries.iter().chain(&self.directory) {
+            if let Some(&pointer) = written.get(&entry.pointer) {
+                index.push((entry.filename_crc, pointer, entry.uncompressed_size));
+                continue;
+            }
+            let pointer = to_u32(HEADER_SIZE as usize + data.len())?;
+            let original = self.block_bytes(entry);
+            let mut blocks = Vec::new();
+            write_blocks(&mut blocks, &self.decompress(entry)?, options)?;
+            if blocks.len() < original.len() {
+                data.extend_from_slice(&blocks);
+                report.recompressed += 1;
+            } else {
+                data.extend_from_slice(original);
+                report.kept += 1;
+            }
+            if !entry.blocks.is_empty() {
+                written.insert(entry.pointer, pointer);
+            }
+            index.push((entry.filename_crc, pointer, entry.uncompressed_size));
+            if entry.filename_crc != DIRECTORY_CRC {
+                tracker.advance(u64::from(entry.uncompressed_size));
+            }
+        }
+
+        let mut buffer = Vec::with_capacity(self.data.len());
+        write_header(&mut buffer, to_u32(HEADER_SIZE as usize + data.len())?)?;
+        buffer.extend_from_slice(&data);
+        let footer = self.timestamp().filter(|_| options.footer);
+        write_index(&mut buffer, index, footer)?;
+        report.after = buffer.len() as u64;
+
+        self.reparse(buffer)?;
+        Ok(report)
+    }
+
+    // Every block of the entry including their headers, which are stored
+    // back to back
+    pub(crate) fn block_bytes(&self, entry: &Entry) -> &[u8] {
+        // Empty entries can point anywhere, even past the end
+        let span = entry.data_span();
+        if span.is_empty() {
+            return &[];
+        }
+        &self.data[span.start as usize..span.end as usize]
+    }
+}
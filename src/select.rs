@@ -0,0 +1,141 @@
+#[cfg(all(feature = "fs", any(unix, windows)))]
+use std::fs::{self, File};
+use std::path::Path;
+#[cfg(all(feature = "fs", any(unix, windows)))]
+use std::path::PathBuf;
+
+#[cfg(all(feature = "tracing", feature = "fs", any(unix, windows)))]
+use tracing::warn;
+
+#[cfg(all(feature = "fs", any(unix, windows)))]
+use crate::LazyArchive;
+use crate::{Archive, Error};
+
+// Zone archives ship these alongside the main zone .wld
+const COMPANION_WLDS: [&str; 2] = ["objects.wld", "lights.wld"];
+#[cfg(all(feature = "fs", any(unix, windows)))]
+const ARCHIVE_EXTENSIONS: [&str; 3] = ["s3d", "eqg", "pfs"];
+
+impl<'a> Archive<'a> {
+    pub fn files_with_extension(&self, extension: &str) -> Vec<&str> {
+        self.filenames
+            .iter()
+            .filter(|filename| has_extension(filename, extension))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// Supports `*` and `?` wildcards, matched case insensitively
+    pub fn glob(&self, pattern: &str) -> Vec<&str> {
+        let pattern = pattern.to_ascii_lowercase().into_bytes();
+        self.filenames
+            .iter()
+            .filter(|filename| wildcard(&pattern, filename.to_ascii_lowercase().as_bytes()))
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn matching(&self, regex: &regex::Regex) -> Vec<&str> {
+        self.filenames
+            .iter()
+            .filter(|filename| regex.is_match(filename))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The main .wld of the archive, preferring the zone's own over its objects
+    /// and lights if it has them
+    pub fn wld(&self) -> Result<Option<Vec<u8>>, Error> {
+        let wlds = self.files_with_extension("wld");
+        let filename = wlds
+            .iter()
+            .find(|filename| {
+                !COMPANION_WLDS
+                    .iter()
+                    .any(|companion| filename.eq_ignore_ascii_case(companion))
+            })
+            .or_else(|| wlds.first());
+        match filename {
+            Some(filename) => self.get(filename),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Finds the archives in `dir` with files matching `pattern`, which works like
+/// `Archive::glob`. Only the entry table and directory of each archive are
+/// read. Subdirectories aren't searched and files that can't be read as an
+/// archive are skipped.
+#[cfg(all(feature = "fs", any(unix, windows)))]
+pub fn search(dir: impl AsRef<Path>, pattern: &str) -> Result<Vec<(PathBuf, String)>, Error> {
+    let pattern = pattern.to_ascii_lowercase().into_bytes();
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_archive = path.to_str().is_some_and(|path| {
+            ARCHIVE_EXTENSIONS
+                .iter()
+                .any(|extension| has_extension(path, extension))
+        });
+        if is_archive {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    let mut found = Vec::new();
+    for path in paths {
+        let archive = match File::open(&path)
+            .map_err(Error::from)
+            .and_then(LazyArchive::open)
+        {
+            Ok(archive) => archive,
+            Err(e) => {
+                warn!("skipping {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        for filename in archive.filenames() {
+            if wildcard(&pattern, filename.to_ascii_lowercase().as_bytes()) {
+                found.push((path.clone(), filename.clone()));
+            }
+        }
+    }
+    Ok(found)
+}
+
+pub(crate) fn has_extension(filename: &str, extension: &str) -> bool {
+    Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+fn wildcard(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Where to resume from if the most recent `*` needs to match more
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
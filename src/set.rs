@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+#[cfg(feature = "fs")]
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use crate::{Archive, Error};
+
+// Suffixes of the archives the client loads alongside a zone's main archive
+#[cfg(feature = "fs")]
+const ZONE_SUFFIXES: [&str; 4] = ["", "_obj", "_2_obj", "_chr"];
+// Newer zones are .eqg archives named the same way
+#[cfg(feature = "fs")]
+const ZONE_EXTENSIONS: [&str; 2] = ["s3d", "eqg"];
+
+/// Several archives viewed as one. Archives mounted later take precedence when
+/// more than one of them holds the same file, so patches can be layered over a
+/// base install.
+#[derive(Debug, Default)]
+pub struct ArchiveSet<'a> {
+    archives: Vec<Archive<'a>>,
+}
+
+impl<'a> ArchiveSet<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mounts all of a zone's .s3d archives that exist, e.g. gfaydark.s3d,
+    /// gfaydark_obj.s3d and gfaydark_chr.s3d
+    #[cfg(feature = "fs")]
+    pub fn read_zone(directory: &Path, zone: &str) -> Result<ArchiveSet<'static>, Error> {
+        let mut set = ArchiveSet::new();
+        for suffix in ZONE_SUFFIXES.iter() {
+            let path = directory.join(format!("{}{}.s3d", zone, suffix));
+            if path.is_file() {
+                set.mount(crate::read(&path)?);
+            }
+        }
+        Ok(set)
+    }
+
+    pub fn mount(&mut self, archive: Archive<'a>) -> &mut Self {
+        self.archives.push(archive);
+        self
+    }
+
+    pub fn archives(&self) -> &[Archive<'a>] {
+        &self.archives
+    }
+
+    /// The archive a file would be read from
+    pub fn archive_for(&self, filename: &str) -> Option<&Archive<'a>> {
+        self.archives
+            .iter()
+            .rev()
+            .find(|archive| archive.contains(filename))
+    }
+
+    pub fn contains(&self, filename: &str) -> bool {
+        self.archive_for(filename).is_some()
+    }
+
+    pub fn get(&self, filename: &str) -> Result<Option<Vec<u8>>, Error> {
+        match self.archive_for(filename) {
+            Some(archive) => archive.get(filename),
+            None => Ok(None),
+        }
+    }
+
+    /// Every file in the set once, with names that differ only by case
+    /// counted as the same file
+    pub fn filenames(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.archives
+            .iter()
+            .flat_map(|archive| archive.filenames().iter())
+            .filter(|filename| seen.insert(filename.to_ascii_lowercase()))
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Opens the archive at `path` along with the companions the client would
+/// load with it, in the same order as the client. Any of a zone's archives
+/// can be given, gfaydark_obj.s3d finds gfaydark.s3d, gfaydark_chr.s3d,
+/// gfaydark.eqg and so on.
+#[cfg(feature = "fs")]
+pub fn related(path: impl AsRef<Path>) -> Result<ArchiveSet<'static>, Error> {
+    let path = path.as_ref();
+    if !path.is_file() {
+        return Err(io::Error::from(io::ErrorKind::NotFound).into());
+    }
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    let zone = ZONE_SUFFIXES
+        .iter()
+        .filter(|suffix| !suffix.is_empty())
+        .filter_map(|suffix| stem.strip_suffix(suffix))
+        // gfaydark_2_obj also ends in _obj
+        .min_by_key(|zone| zone.len())
+        .unwrap_or(stem);
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut set = ArchiveSet::new();
+    for suffix in ZONE_SUFFIXES.iter() {
+        for extension in ZONE_EXTENSIONS.iter() {
+            let companion = directory.join(format!("{}{}.{}", zone, suffix, extension));
+            if companion.is_file() {
+                set.mount(crate::read(&companion)?);
+            }
+        }
+    }
+    Ok(set)
+}
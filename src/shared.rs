@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak};
+use std::thread::{self, JoinHandle};
+
+use crate::{Archive, Error};
+
+// Bytes of decompressed data kept by default
+const CACHE_CAPACITY: usize = 64 * 1024 * 1024;
+
+struct Cached {
+    data: Arc<Vec<u8>>,
+    last_used: AtomicU64,
+}
+
+// Hits only take the read lock, so recency is tracked with a counter that
+// can be bumped through a shared reference rather than by reordering a list
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<usize, Cached>,
+    size: usize,
+}
+
+/// An archive that can be shared between threads with `Arc`, keeping recently
+/// decompressed files so that concurrent `get` calls for the same texture
+/// don't inflate it again
+pub struct SharedArchive<'a> {
+    archive: Archive<'a>,
+    cache: RwLock<Cache>,
+    // One per entry, held while it's inflated so other threads missing the
+    // cache for it wait and take the same data rather than inflating it too.
+    // Files too big to cache are still shared while anyone holds them.
+    inflating: Vec<Mutex<Weak<Vec<u8>>>>,
+    capacity: usize,
+    clock: AtomicU64,
+}
+
+impl<'a> SharedArchive<'a> {
+    pub fn new(archive: Archive<'a>) -> Self {
+        Self::with_capacity(archive, CACHE_CAPACITY)
+    }
+
+    /// Files larger than the capacity are never cached
+    pub fn with_capacity(archive: Archive<'a>, capacity: usize) -> Self {
+        Self {
+            inflating: archive
+                .entries
+                .iter()
+                .map(|_| Mutex::new(Weak::new()))
+                .collect(),
+            archive,
+            cache: RwLock::new(Cache::default()),
+            capacity,
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    pub fn archive(&self) -> &Archive<'a> {
+        &self.archive
+    }
+
+    pub fn get(&self, filename: &str) -> Result<Option<Arc<Vec<u8>>>, Error> {
+        self.archive
+            .index_of(filename)
+            .map(|position| self.get_at(position))
+            .transpose()
+    }
+
+    /// Decompresses files into the cache in archive order until the next one
+    /// wouldn't fit, for workloads like benchmarks that read everything anyway
+    pub fn prefetch(&self) -> Result<(), Error> {
+        for (position, entry) in self.archive.entries.iter().enumerate() {
+            if self.read_cache().size + entry.uncompressed_size as usize > self.capacity {
+                break;
+            }
+            self.get_at(position)?;
+        }
+        Ok(())
+    }
+
+    /// Whether a file is in the cache, so `get` will return it without
+    /// inflating anything
+    pub fn is_cached(&self, filename: &str) -> bool {
+        match self.archive.index_of(filename) {
+            Some(position) => self.read_cache().entries.contains_key(&position),
+            None => false,
+        }
+    }
+
+    fn get_at(&self, position: usize) -> Result<Arc<Vec<u8>>, Error> {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(cached) = self.read_cache().entries.get(&position) {
+            cached.last_used.store(tick, Ordering::Relaxed);
+            return Ok(cached.data.clone());
+        }
+
+        let mut inflating = self.inflating[position]
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(data) = inflating.upgrade() {
+            return Ok(data);
+        }
+        let data = Arc::new(self.archive.decompress(&self.archive.entries[position])?);
+        *inflating = Arc::downgrade(&data);
+        if data.len() <= self.capacity {
+            self.insert(position, data.clone(), tick);
+        }
+        Ok(data)
+    }
+
+    pub fn clear_cache(&self) {
+        let mut cache = self.write_cache();
+        cache.entries.clear();
+        cache.size = 0;
+    }
+
+    fn insert(&self, position: usize, data: Arc<Vec<u8>>, tick: u64) {
+        let mut cache = self.write_cache();
+        while cache.size + data.len() > self.capacity {
+            let oldest = cache
+                .entries
+                .iter()
+                .min_by_key(|(_, cached)| cached.last_used.load(Ordering::Relaxed))
+                .map(|(&position, _)| position);
+            match oldest.and_then(|oldest| cache.entries.remove(&oldest)) {
+                Some(evicted) => cache.size -= evicted.data.len(),
+                None => break,
+            }
+        }
+        cache.size += data.len();
+        cache.entries.insert(
+            position,
+            Cached {
+                data,
+                last_used: AtomicU64::new(tick),
+            },
+        );
+    }
+
+    // A panic while holding the lock can't leave the cache inconsistent
+    // enough to matter, so poisoning is ignored
+    fn read_cache(&self) -> RwLockReadGuard<'_, Cache> {
+        self.cache.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_cache(&self) -> RwLockWriteGuard<'_, Cache> {
+        self.cache.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SharedArchive<'static> {
+    /// Decompresses the named files into the cache on another thread, so a
+    /// loader can get on with something else while a zone's assets inflate.
+    /// Files that aren't in the archive are skipped, and a `get` for a file
+    /// that hasn't been reached yet just inflates it itself.
+    pub fn prefetch_files(self: &Arc<Self>, filenames: &[&str]) -> JoinHandle<Result<(), Error>> {
+        let archive = self.clone();
+        let positions: Vec<_> = filenames
+            .iter()
+            .filter_map(|filename| archive.archive.index_of(filename))
+            .collect();
+        thread::spawn(move || {
+            for position in positions {
+                archive.get_at(position)?;
+            }
+            Ok(())
+        })
+    }
+}
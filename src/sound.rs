@@ -0,0 +1,188 @@
+use std::convert::TryFrom;
+use std::io;
+use std::time::Duration;
+
+use crate::parser::{read_u16, read_u32};
+use crate::select::has_extension;
+use crate::{Archive, Error};
+
+// Enough of the start of a file to tell the formats below apart
+const HEADER_SIZE: u64 = 12;
+const WAV_HEADER_SIZE: u32 = 44;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundFormat {
+    Wav,
+    /// Extended MIDI used by the Miles sound system for music
+    Xmi,
+    Midi,
+    /// PCM samples stored without a RIFF header, recognised by a .wav extension
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+}
+
+impl PcmFormat {
+    pub fn duration(&self, data_size: usize) -> Duration {
+        let bytes_per_second = u64::from(self.sample_rate)
+            * u64::from(self.channels)
+            * u64::from(self.bits_per_sample / 8);
+        if bytes_per_second == 0 {
+            return Duration::default();
+        }
+        Duration::from_secs_f64(data_size as f64 / bytes_per_second as f64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoundInfo {
+    pub format: SoundFormat,
+    /// Only known for WAVs with a valid header
+    pub pcm: Option<PcmFormat>,
+    pub duration: Option<Duration>,
+}
+
+impl SoundInfo {
+    pub fn detect(filename: &str, data: &[u8]) -> Option<Self> {
+        format(filename, data).map(|format| Self::with_format(format, data))
+    }
+
+    fn with_format(format: SoundFormat, data: &[u8]) -> Self {
+        let wav = match format {
+            SoundFormat::Wav => wav(data),
+            _ => None,
+        };
+        Self {
+            format,
+            pcm: wav.map(|(pcm, _)| pcm),
+            duration: wav.map(|(pcm, data_size)| pcm.duration(data_size)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sound {
+    pub filename: String,
+    pub info: SoundInfo,
+    pub data: Vec<u8>,
+}
+
+impl Sound {
+    /// Headerless samples are wrapped in a RIFF header describing them as
+    /// `pcm`, which the archive doesn't record. Music isn't PCM and gives `None`.
+    /// Fails when `pcm` or the number of samples don't fit in the header.
+    pub fn to_wav(&self, pcm: PcmFormat) -> Result<Option<Vec<u8>>, Error> {
+        match self.info.format {
+            SoundFormat::Wav => Ok(Some(self.data.clone())),
+            SoundFormat::Raw => Ok(Some(wrap(&self.data, pcm)?)),
+            SoundFormat::Xmi | SoundFormat::Midi => Ok(None),
+        }
+    }
+}
+
+impl<'a> Archive<'a> {
+    /// Sound effects and music from snd*.pfs archives. Only the first block of
+    /// anything that isn't a sound is inflated.
+    pub fn sounds(&self) -> impl Iterator<Item = Result<Sound, Error>> + '_ {
+        self.filenames
+            .iter()
+            .zip(self.entries.iter())
+            .filter_map(move |(filename, entry)| {
+                let header = match entry.peek(&self.data, &*self.inflater, HEADER_SIZE) {
+                    Ok(header) => header,
+                    Err(e) => return Some(Err(e)),
+                };
+                let format = format(filename, &header)?;
+                Some(self.decompress(entry).map(|data| Sound {
+                    filename: filename.clone(),
+                    info: SoundInfo::with_format(format, &data),
+                    data,
+                }))
+            })
+    }
+}
+
+fn format(filename: &str, data: &[u8]) -> Option<SoundFormat> {
+    if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WAVE") {
+        Some(SoundFormat::Wav)
+    } else if data.starts_with(b"FORM") && data.get(8..12) == Some(b"XDIR") {
+        Some(SoundFormat::Xmi)
+    } else if data.starts_with(b"MThd") {
+        Some(SoundFormat::Midi)
+    } else if has_extension(filename, "wav") {
+        Some(SoundFormat::Raw)
+    } else {
+        None
+    }
+}
+
+// The format from the "fmt " chunk and the size of the "data" chunk
+fn wav(data: &[u8]) -> Option<(PcmFormat, usize)> {
+    let mut pcm = None;
+    let mut offset = 12;
+    while let (Some(id), Some(size)) = (data.get(offset..offset + 4), read_u32(data, offset + 4)) {
+        let body = offset + 8;
+        match id {
+            b"fmt " => {
+                pcm = Some(PcmFormat {
+                    channels: read_u16(data, body + 2)?,
+                    sample_rate: read_u32(data, body + 4)?,
+                    bits_per_sample: read_u16(data, body + 14)?,
+                })
+            }
+            // Truncated files are common enough to report what's actually there
+            b"data" => return Some((pcm?, (size as usize).min(data.len() - body))),
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        offset = body.checked_add(size as usize + (size as usize & 1))?;
+    }
+    None
+}
+
+fn wrap(samples: &[u8], pcm: PcmFormat) -> io::Result<Vec<u8>> {
+    let too_large = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} bytes of {:?} samples don't fit in a WAV header",
+                samples.len(),
+                pcm
+            ),
+        )
+    };
+    let block_align = pcm
+        .channels
+        .checked_mul(pcm.bits_per_sample / 8)
+        .ok_or_else(too_large)?;
+    let byte_rate = pcm
+        .sample_rate
+        .checked_mul(block_align.into())
+        .ok_or_else(too_large)?;
+    let data_size = u32::try_from(samples.len()).map_err(|_| too_large())?;
+    let riff_size = data_size
+        .checked_add(WAV_HEADER_SIZE - 8)
+        .ok_or_else(too_large)?;
+
+    let mut wav = Vec::with_capacity(WAV_HEADER_SIZE as usize + samples.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&riff_size.to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // Uncompressed PCM
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&pcm.channels.to_le_bytes());
+    wav.extend_from_slice(&pcm.sample_rate.to_le_bytes());
+    wav.extend_from_slice(&byte_rate.to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&pcm.bits_per_sample.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_size.to_le_bytes());
+    wav.extend_from_slice(samples);
+    Ok(wav)
+}
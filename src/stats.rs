@@ -0,0 +1,148 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{Archive, Error};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveStats {
+    pub entry_count: usize,
+    pub block_count: usize,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    /// Compressed size as a fraction of the uncompressed size
+    pub compression_ratio: f64,
+    /// Filename and uncompressed size of the biggest file
+    pub largest_entry: Option<(String, u32)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtensionStats {
+    pub count: usize,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+}
+
+/// Where the space in an archive goes, for trimming down bloated ones
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub stats: ArchiveStats,
+    /// Keyed by lowercased extension, files without one are under ""
+    pub by_extension: BTreeMap<String, ExtensionStats>,
+    /// Empty files aren't counted as duplicates of each other
+    pub duplicates: Vec<Duplicates>,
+    /// Filename and uncompressed size of the biggest files, biggest first
+    pub largest: Vec<(String, u32)>,
+}
+
+impl Analysis {
+    /// Uncompressed bytes that keeping only one of each duplicate would save
+    pub fn duplicate_size(&self) -> u64 {
+        self.duplicates
+            .iter()
+            .map(|group| u64::from(group.size) * (group.filenames.len() as u64 - 1))
+            .sum()
+    }
+}
+
+/// Files with the same contents, named in archive order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Duplicates {
+    pub size: u32,
+    pub filenames: Vec<String>,
+}
+
+impl<'a> Archive<'a> {
+    /// Inflates every file to find duplicates, which are matched by size and
+    /// CRC-32 of their contents. `top` is how many of the largest files to list.
+    pub fn analyze(&self, top: usize) -> Result<Analysis, Error> {
+        let mut by_extension: BTreeMap<String, ExtensionStats> = BTreeMap::new();
+        for (filename, entry) in self.filenames.iter().zip(self.entries.iter()) {
+            let extension = match filename.rsplit_once('.') {
+                Some((_, extension)) => extension.to_ascii_lowercase(),
+                None => String::new(),
+            };
+            let stats = by_extension.entry(extension).or_default();
+            stats.count += 1;
+            stats.compressed_size += u64::from(entry.compressed_size());
+            stats.uncompressed_size += u64::from(entry.uncompressed_size);
+        }
+
+        // Entries sharing blocks are only inflated once
+        let mut checksums: HashMap<u32, u32> = HashMap::new();
+        let mut groups: HashMap<(u32, u32), Vec<String>> = HashMap::new();
+        let mut order = Vec::new();
+        for (filename, entry) in self.filenames.iter().zip(self.entries.iter()) {
+            if entry.uncompressed_size == 0 {
+                continue;
+            }
+            let checksum = match checksums.get(&entry.pointer) {
+                Some(&checksum) => checksum,
+                None => {
+                    let checksum = crc32fast::hash(&self.decompress(entry)?);
+                    checksums.insert(entry.pointer, checksum);
+                    checksum
+                }
+            };
+            let key = (entry.uncompressed_size, checksum);
+            let group = groups.entry(key).or_default();
+            if group.is_empty() {
+                order.push(key);
+            }
+            group.push(filename.clone());
+        }
+        let duplicates = order
+            .iter()
+            .filter_map(|key| Some((key.0, groups.remove(key)?)))
+            .filter(|(_, filenames)| filenames.len() > 1)
+            .map(|(size, filenames)| Duplicates { size, filenames })
+            .collect();
+
+        let mut largest: Vec<_> = self
+            .filenames
+            .iter()
+            .zip(self.entries.iter())
+            .map(|(filename, entry)| (filename.clone(), entry.uncompressed_size))
+            .collect();
+        largest.sort_by_key(|(_, size)| Reverse(*size));
+        largest.truncate(top);
+
+        Ok(Analysis {
+            stats: self.stats(),
+            by_extension,
+            duplicates,
+            largest,
+        })
+    }
+
+    pub fn stats(&self) -> ArchiveStats {
+        let compressed_size: u64 = self
+            .entries
+            .iter()
+            .map(|entry| u64::from(entry.compressed_size()))
+            .sum();
+        let uncompressed_size: u64 = self
+            .entries
+            .iter()
+            .map(|entry| u64::from(entry.uncompressed_size))
+            .sum();
+        let largest_entry = self
+            .filenames
+            .iter()
+            .zip(self.entries.iter())
+            .max_by_key(|(_, entry)| entry.uncompressed_size)
+            .map(|(filename, entry)| (filename.clone(), entry.uncompressed_size));
+
+        ArchiveStats {
+            entry_count: self.entries.len(),
+            block_count: self.entries.iter().map(|entry| entry.blocks.len()).sum(),
+            compressed_size,
+            uncompressed_size,
+            compression_ratio: if uncompressed_size == 0 {
+                1.0
+            } else {
+                compressed_size as f64 / uncompressed_size as f64
+            },
+            largest_entry,
+        }
+    }
+}
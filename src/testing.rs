@@ -0,0 +1,88 @@
+//! Synthetic archives for tests, so neither this crate nor anything built on
+//! it needs to ship files from the game as fixtures
+
+use crate::parser::{FOOTER_SIZE, FOOTER_STRING};
+use crate::{ArchiveBuilder, WriteOptions};
+
+/// Block sizes worth covering, from degenerate to larger than the client uses
+pub const EDGE_CASE_BLOCK_SIZES: [usize; 5] = [1, 7, 4096, 8192, 65536];
+
+const EXTENSIONS: [&str; 5] = ["bmp", "dds", "wld", "mod", "txt"];
+
+/// Always the same bytes for the same files and options
+pub fn build(files: &[(String, Vec<u8>)], options: WriteOptions) -> Vec<u8> {
+    let mut builder = ArchiveBuilder::with_options(options);
+    for (name, data) in files {
+        builder.add_file(name, data.as_slice());
+    }
+    builder
+        .in_memory()
+        .expect("invalid write options for a synthetic archive")
+}
+
+/// .eqg archives are often written without a footer. Archives that don't
+/// have one are returned as they are.
+pub fn without_footer(mut archive: Vec<u8>) -> Vec<u8> {
+    let start = archive.len().saturating_sub(FOOTER_SIZE);
+    if archive.len() >= FOOTER_SIZE && archive[start..].starts_with(FOOTER_STRING) {
+        archive.truncate(start);
+    }
+    archive
+}
+
+/// Files sized around the edges of a block, including an empty one
+pub fn edge_case_files(block_size: usize) -> Vec<(String, Vec<u8>)> {
+    let sizes = [
+        0,
+        1,
+        block_size.saturating_sub(1),
+        block_size,
+        block_size + 1,
+        block_size * 3,
+    ];
+    sizes
+        .iter()
+        .enumerate()
+        .map(|(n, &size)| (format!("edge_{}.bin", n), pattern(n as u64, size)))
+        .collect()
+}
+
+/// The same seed always gives the same files, none of them empty
+pub fn random_files(seed: u64, count: usize, max_size: usize) -> Vec<(String, Vec<u8>)> {
+    let mut rng = Rng::new(seed);
+    (0..count)
+        .map(|n| {
+            let extension = EXTENSIONS[rng.next() as usize % EXTENSIONS.len()];
+            let size = 1 + rng.next() as usize % max_size.max(1);
+            // Alternate between data that compresses well and data that doesn't
+            let data = if rng.next() & 1 == 0 {
+                pattern(rng.next(), size)
+            } else {
+                (0..size).map(|_| rng.next() as u8).collect()
+            };
+            (format!("file_{}.{}", n, extension), data)
+        })
+        .collect()
+}
+
+// A repeating run of bytes, which deflates far better than noise
+fn pattern(seed: u64, size: usize) -> Vec<u8> {
+    (0..size).map(|i| ((i / 16) as u64 ^ seed) as u8).collect()
+}
+
+// xorshift64, good enough for test data without pulling in a dependency
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Zero is the one state xorshift can't leave
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
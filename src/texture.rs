@@ -0,0 +1,83 @@
+use crate::parser::{read_u16, read_u32};
+use crate::select::has_extension;
+use crate::{Archive, Error};
+
+// Enough of the start of a file to find the dimensions of any format below
+const HEADER_SIZE: u64 = 26;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    Bmp,
+    Dds,
+    /// Targa files have no magic number so they're only recognised by extension
+    Tga,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureInfo {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TextureInfo {
+    /// Only the first few bytes of the file are needed
+    pub fn detect(filename: &str, data: &[u8]) -> Option<Self> {
+        let (format, width, height) = if data.starts_with(b"BM") {
+            // Bottom up bitmaps have a positive height, top down ones a negative one
+            (
+                TextureFormat::Bmp,
+                read_u32(data, 18)? as i32,
+                read_u32(data, 22)? as i32,
+            )
+        } else if data.starts_with(b"DDS ") {
+            (
+                TextureFormat::Dds,
+                read_u32(data, 16)? as i32,
+                read_u32(data, 12)? as i32,
+            )
+        } else if has_extension(filename, "tga") {
+            (
+                TextureFormat::Tga,
+                read_u16(data, 12)?.into(),
+                read_u16(data, 14)?.into(),
+            )
+        } else {
+            return None;
+        };
+        Some(Self {
+            format,
+            width: width.unsigned_abs(),
+            height: height.unsigned_abs(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Texture {
+    pub filename: String,
+    pub info: TextureInfo,
+    pub data: Vec<u8>,
+}
+
+impl<'a> Archive<'a> {
+    /// Files are recognised by their contents rather than their extension.
+    /// Only the first block of anything that isn't a texture is inflated.
+    pub fn textures(&self) -> impl Iterator<Item = Result<Texture, Error>> + '_ {
+        self.filenames
+            .iter()
+            .zip(self.entries.iter())
+            .filter_map(move |(filename, entry)| {
+                let header = match entry.peek(&self.data, &*self.inflater, HEADER_SIZE) {
+                    Ok(header) => header,
+                    Err(e) => return Some(Err(e)),
+                };
+                let info = TextureInfo::detect(filename, &header)?;
+                Some(self.decompress(entry).map(|data| Texture {
+                    filename: filename.clone(),
+                    info,
+                    data,
+                }))
+            })
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(feature = "fs")]
+use std::fs::{self, File};
+use std::io::Read;
+#[cfg(feature = "fs")]
+use std::path::PathBuf;
+
+use crate::{Archive, ArchiveSet, Error};
+
+/// Somewhere assets are loaded from by name, so an engine can switch between
+/// archives and loose files without caring which it has. Lookups ignore case
+/// like the client's, unless an archive was read with case sensitive lookups.
+/// `Archive::open` is its own method returning a seekable file, call this one
+/// as `Vfs::open(&archive, name)`.
+pub trait Vfs {
+    /// Fails with `Error::FileNotFound` when there's no such file
+    fn open(&self, filename: &str) -> Result<Box<dyn Read + '_>, Error>;
+
+    fn exists(&self, filename: &str) -> bool;
+
+    fn list(&self) -> Vec<String>;
+
+    fn read(&self, filename: &str) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        self.open(filename)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl Vfs for Archive<'_> {
+    fn open(&self, filename: &str) -> Result<Box<dyn Read + '_>, Error> {
+        match self.get_reader(filename) {
+            Some(reader) => Ok(Box::new(reader)),
+            None => Err(Error::FileNotFound(filename.to_string())),
+        }
+    }
+
+    fn exists(&self, filename: &str) -> bool {
+        self.contains(filename)
+    }
+
+    fn list(&self) -> Vec<String> {
+        self.filenames().to_vec()
+    }
+}
+
+impl Vfs for ArchiveSet<'_> {
+    fn open(&self, filename: &str) -> Result<Box<dyn Read + '_>, Error> {
+        match self.archive_for(filename) {
+            Some(archive) => Vfs::open(archive, filename),
+            None => Err(Error::FileNotFound(filename.to_string())),
+        }
+    }
+
+    fn exists(&self, filename: &str) -> bool {
+        self.contains(filename)
+    }
+
+    fn list(&self) -> Vec<String> {
+        self.filenames().into_iter().map(String::from).collect()
+    }
+}
+
+/// Files in a directory on disk, like an archive that's been extracted. Only
+/// files directly in the directory are listed, archives don't have folders.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct LooseFiles {
+    root: PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl LooseFiles {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // An exact match is tried first, then any name that differs only by
+    // case, for filesystems that care
+    fn path(&self, filename: &str) -> Result<Option<PathBuf>, Error> {
+        if filename.is_empty() || filename.contains(['/', '\\']) || filename == ".." {
+            return Err(Error::UnsafePath(filename.to_string()));
+        }
+        let exact = self.root.join(filename);
+        if exact.is_file() {
+            return Ok(Some(exact));
+        }
+        Ok(self
+            .list()
+            .into_iter()
+            .find(|name| name.eq_ignore_ascii_case(filename))
+            .map(|name| self.root.join(name)))
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Vfs for LooseFiles {
+    fn open(&self, filename: &str) -> Result<Box<dyn Read + '_>, Error> {
+        match self.path(filename)? {
+            Some(path) => Ok(Box::new(File::open(path)?)),
+            None => Err(Error::FileNotFound(filename.to_string())),
+        }
+    }
+
+    fn exists(&self, filename: &str) -> bool {
+        matches!(self.path(filename), Ok(Some(_)))
+    }
+
+    // Names that aren't valid UTF-8 are skipped
+    fn list(&self) -> Vec<String> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut names: Vec<_> = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        names
+    }
+}
@@ -0,0 +1,462 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::io::{self, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flate2::write::ZlibEncoder;
+
+use crate::crc::filename_crc;
+use crate::parser::{
+    is_zlib, unnamed, BLOCK_HEADER_SIZE, DIRECTORY_CRC, FOOTER_STRING, HEADER_SIZE, PFS_MAGIC,
+    PFS_VERSION,
+};
+use crate::progress::{Progress, Tracker};
+
+// Files are split into blocks of at most this many uncompressed bytes
+const BLOCK_SIZE: usize = 8192;
+
+/// Largest block size that can be written. Block sizes are stored as u32 and
+/// deflate can grow incompressible data, so this leaves plenty of room, and
+/// blocks this big already compress about as well as a whole file would.
+pub const MAX_BLOCK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Footer timestamp of archives from `ArchiveBuilder::in_memory`, in seconds
+/// since the Unix epoch, unless another one is set. Any fixed time would do,
+/// this one is 2001-09-09.
+pub const DETERMINISTIC_TIMESTAMP: u32 = 1_000_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Fast,
+    Default,
+    Best,
+    /// Deflate level from 0 to 9
+    Level(u32),
+    /// Data is wrapped in zlib without being deflated, for files that are
+    /// already compressed like DDS textures
+    Stored,
+    /// Blocks are written raw like some community packers do. Smaller than
+    /// `Stored` but the client may not read them.
+    Uncompressed,
+}
+
+impl From<Compression> for flate2::Compression {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Fast => Self::fast(),
+            Compression::Default => Self::default(),
+            Compression::Best => Self::best(),
+            Compression::Level(level) => Self::new(level.min(9)),
+            Compression::Stored | Compression::Uncompressed => Self::none(),
+        }
+    }
+}
+
+/// The order files are written in, which is also the order of the directory.
+/// The entry table is always sorted by CRC since the client binary searches it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileOrder {
+    #[default]
+    Insertion,
+    /// Case insensitive, like the client's own lookups
+    Name,
+    Crc,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WriteOptions {
+    pub compression: Compression,
+    /// Larger blocks compress big files like WLDs better, smaller ones mean
+    /// less to fetch for ranged reads. The client's own archives use 8192, the
+    /// default, and anything from 1 to `MAX_BLOCK_SIZE` is accepted.
+    pub block_size: usize,
+    /// Called as each file is written
+    pub progress: Option<Progress>,
+    /// Files with the same contents share the same blocks. Only done by
+    /// `ArchiveBuilder`, `ArchiveWriter` has written a file out before it
+    /// could know it's a duplicate.
+    pub deduplicate: bool,
+    /// Also only done by `ArchiveBuilder`, `ArchiveWriter` writes files as
+    /// they're added
+    pub ordering: FileOrder,
+    /// Threads deflating files, and the blocks of each file, with the
+    /// `parallel` feature, 0 uses one per core. `ArchiveWriter` always uses one.
+    pub threads: usize,
+    /// The "STEVE" footer holding when the archive was written, which .s3d
+    /// files always have and .eqg files for newer clients don't
+    pub footer: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self {
+            compression: Compression::Default,
+            block_size: BLOCK_SIZE,
+            progress: None,
+            deduplicate: true,
+            ordering: FileOrder::Insertion,
+            threads: 1,
+            footer: true,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Matches the layout of the retail packer for clients that reject
+    /// anything else: 8192 byte blocks deflated at zlib's default level, every
+    /// file with its own blocks, the directory after the last file and a
+    /// "STEVE" footer. Only `progress` and `threads` can be changed without
+    /// breaking that.
+    pub fn retail() -> Self {
+        Self {
+            compression: Compression::Default,
+            block_size: BLOCK_SIZE,
+            progress: None,
+            deduplicate: false,
+            ordering: FileOrder::Insertion,
+            threads: 1,
+            footer: true,
+        }
+    }
+
+    /// The retail layout as newer clients expect it in .eqg files, the same
+    /// but without a footer
+    pub fn eqg() -> Self {
+        Self {
+            footer: false,
+            ..Self::retail()
+        }
+    }
+}
+
+// Entries recovered without a name keep their CRC and stay out of the
+// directory, as they were in the archive they came from
+#[derive(Debug)]
+pub(crate) enum Name {
+    Listed(Vec<u8>),
+    Unlisted(u32),
+}
+
+impl Name {
+    pub(crate) fn crc(&self) -> u32 {
+        match self {
+            Name::Listed(name) => filename_crc(name),
+            Name::Unlisted(crc) => *crc,
+        }
+    }
+
+    pub(crate) fn listed(&self) -> Option<&[u8]> {
+        match self {
+            Name::Listed(name) => Some(name),
+            Name::Unlisted(_) => None,
+        }
+    }
+
+    // Unlisted entries sort by the name `Archive::filenames` gives them
+    fn sort_key(&self) -> Vec<u8> {
+        match self {
+            Name::Listed(name) => name.to_ascii_lowercase(),
+            Name::Unlisted(crc) => unnamed(*crc).into_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ArchiveBuilder {
+    files: Vec<(Name, Vec<u8>)>,
+    options: WriteOptions,
+    timestamp: Option<SystemTime>,
+}
+
+impl ArchiveBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(options: WriteOptions) -> Self {
+        Self {
+            files: Vec::new(),
+            options,
+            timestamp: None,
+        }
+    }
+
+    /// Stamped into the footer instead of the time the archive is written
+    pub fn set_timestamp(&mut self, timestamp: SystemTime) -> &mut Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn add_file(&mut self, name: &str, data: impl Into<Vec<u8>>) -> &mut Self {
+        self.add_file_raw(name.as_bytes(), data)
+    }
+
+    /// Names are written as given without any encoding, see `Archive::raw_filenames`
+    pub fn add_file_raw(&mut self, name: &[u8], data: impl Into<Vec<u8>>) -> &mut Self {
+        self.add_entry(Name::Listed(name.to_vec()), data)
+    }
+
+    pub(crate) fn add_entry(&mut self, name: Name, data: impl Into<Vec<u8>>) -> &mut Self {
+        self.files.push((name, data.into()));
+        self
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn write_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        self.write(&mut file)
+    }
+
+    /// The whole archive as bytes, for tests that shouldn't need fixture files.
+    /// The same files and options always give the same bytes, the footer gets
+    /// `DETERMINISTIC_TIMESTAMP` rather than the current time. Only fails on
+    /// invalid options.
+    pub fn in_memory(&self) -> io::Result<Vec<u8>> {
+        let timestamp = UNIX_EPOCH + Duration::from_secs(DETERMINISTIC_TIMESTAMP.into());
+        let mut buffer = Vec::new();
+        self.write_stamped(&mut buffer, self.timestamp.unwrap_or(timestamp))?;
+        Ok(buffer)
+    }
+
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_stamped(writer, self.timestamp.unwrap_or_else(SystemTime::now))
+    }
+
+    fn write_stamped<W: Write>(&self, mut writer: W, timestamp: SystemTime) -> io::Result<()> {
+        validate(&self.options)?;
+
+        #[cfg(feature = "parallel")]
+        let (data, entries) = if self.options.threads == 1 {
+            self.write_blocks()?
+        } else {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(self.options.threads)
+                .build()
+                .map_err(io::Error::other)?
+                .install(|| self.write_blocks())?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let (data, entries) = self.write_blocks()?;
+
+        write_header(&mut writer, to_u32(HEADER_SIZE as usize + data.len())?)?;
+        writer.write_all(&data)?;
+        let footer = Some(timestamp).filter(|_| self.options.footer);
+        write_index(&mut writer, entries, footer)?;
+        writer.flush()
+    }
+
+    // The block data of every file followed by the directory, and the entries
+    // pointing into it
+    fn write_blocks(&self) -> io::Result<(Vec<u8>, Vec<IndexEntry>)> {
+        let mut files: Vec<_> = self.files.iter().collect();
+        match self.options.ordering {
+            FileOrder::Insertion => {}
+            FileOrder::Name => files.sort_by_key(|(name, _)| name.sort_key()),
+            FileOrder::Crc => files.sort_by_key(|(name, _)| name.crc()),
+        }
+
+        // The first file with the same contents, whose blocks are shared.
+        // Empty files have no blocks to share.
+        let mut first: HashMap<&[u8], usize> = HashMap::new();
+        let sources: Vec<_> = files
+            .iter()
+            .enumerate()
+            .map(|(position, (_, contents))| {
+                if self.options.deduplicate && !contents.is_empty() {
+                    *first.entry(contents.as_slice()).or_insert(position)
+                } else {
+                    position
+                }
+            })
+            .collect();
+
+        let mut data = Vec::new();
+        let mut entries = Vec::new();
+        let mut pointers = Vec::with_capacity(files.len());
+        let compressed = self.compress_files(&files, &sources)?;
+        for (position, ((name, contents), blocks)) in files.iter().zip(compressed).enumerate() {
+            let pointer = match blocks {
+                Some(blocks) => {
+                    let pointer = to_u32(HEADER_SIZE as usize + data.len())?;
+                    data.extend_from_slice(&blocks);
+                    pointer
+                }
+                None => pointers[sources[position]],
+            };
+            pointers.push(pointer);
+            entries.push((name.crc(), pointer, to_u32(contents.len())?));
+        }
+
+        // The directory is stored as the final entry, after all files
+        let listed: Vec<_> = files.iter().filter_map(|(name, _)| name.listed()).collect();
+        let directory = directory(listed.into_iter());
+        let pointer = to_u32(HEADER_SIZE as usize + data.len())?;
+        write_blocks(&mut data, &directory, &self.options)?;
+        entries.push((DIRECTORY_CRC, pointer, to_u32(directory.len())?));
+        Ok((data, entries))
+    }
+
+    // The blocks of each file, or `None` for files that share an earlier
+    // file's. With more than one thread whole files are compressed in
+    // parallel, and progress is reported as each one finishes.
+    fn compress_files(
+        &self,
+        files: &[&(Name, Vec<u8>)],
+        sources: &[usize],
+    ) -> io::Result<Vec<Option<Vec<u8>>>> {
+        let tracker = Mutex::new(Tracker::new(self.options.progress, Some(files.len())));
+        let compress = |(position, (_, contents)): (usize, &&(Name, Vec<u8>))| {
+            let blocks = if sources[position] == position {
+                let mut blocks = Vec::new();
+                write_blocks(&mut blocks, contents, &self.options)?;
+                Some(blocks)
+            } else {
+                None
+            };
+            tracker
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .advance(contents.len() as u64);
+            Ok(blocks)
+        };
+
+        #[cfg(feature = "parallel")]
+        if self.options.threads != 1 {
+            use rayon::prelude::*;
+
+            return files.par_iter().enumerate().map(compress).collect();
+        }
+        files.iter().enumerate().map(compress).collect()
+    }
+}
+
+pub(crate) fn validate(options: &WriteOptions) -> io::Result<()> {
+    if options.block_size == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "block size must be greater than zero",
+        ));
+    }
+    if options.block_size > MAX_BLOCK_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("block size must be at most {} bytes", MAX_BLOCK_SIZE),
+        ));
+    }
+    Ok(())
+}
+
+// Pointers and sizes are stored as u32, so nothing can be addressed past
+// 4 GiB. They're checked rather than cast so that writing an archive that
+// big fails instead of wrapping around into a corrupt one.
+pub(crate) fn to_u32(value: usize) -> io::Result<u32> {
+    u32::try_from(value).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::FileTooLarge,
+            format!(
+                "offset or size of {} bytes is past the 4 GiB an archive can address",
+                value
+            ),
+        )
+    })
+}
+
+pub(crate) fn write_header<W: Write>(writer: &mut W, pointer: u32) -> io::Result<()> {
+    writer.write_all(&pointer.to_le_bytes())?;
+    writer.write_all(&PFS_MAGIC.to_le_bytes())?;
+    writer.write_all(&PFS_VERSION.to_le_bytes())
+}
+
+// CRC, pointer and uncompressed size
+pub(crate) type IndexEntry = (u32, u32, u32);
+
+// Writes the entry table and the footer, if there's a timestamp for one, that
+// follow the block data
+pub(crate) fn write_index<W: Write>(
+    writer: &mut W,
+    mut entries: Vec<IndexEntry>,
+    footer: Option<SystemTime>,
+) -> io::Result<()> {
+    // The client expects the entry table to be sorted by CRC
+    entries.sort_by_key(|&(crc, _, _)| crc);
+
+    writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+    for (crc, pointer, uncompressed_size) in entries {
+        writer.write_all(&crc.to_le_bytes())?;
+        writer.write_all(&pointer.to_le_bytes())?;
+        writer.write_all(&uncompressed_size.to_le_bytes())?;
+    }
+
+    if let Some(timestamp) = footer {
+        writer.write_all(FOOTER_STRING)?;
+        writer.write_all(&footer_timestamp(timestamp).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_blocks(
+    out: &mut Vec<u8>,
+    contents: &[u8],
+    options: &WriteOptions,
+) -> io::Result<()> {
+    #[cfg(feature = "parallel")]
+    if options.threads != 1 {
+        use rayon::prelude::*;
+
+        let blocks = contents
+            .par_chunks(options.block_size)
+            .map(|chunk| block(chunk, options.compression))
+            .collect::<io::Result<Vec<_>>>()?;
+        for block in blocks {
+            out.extend_from_slice(&block);
+        }
+        return Ok(());
+    }
+    for chunk in contents.chunks(options.block_size) {
+        out.extend_from_slice(&block(chunk, options.compression)?);
+    }
+    Ok(())
+}
+
+// A single compressed block, including its header
+pub(crate) fn block(chunk: &[u8], compression: Compression) -> io::Result<Vec<u8>> {
+    // A raw block that happens to start like a zlib stream would be inflated
+    // when read, so that one gets wrapped instead
+    let compressed = if compression == Compression::Uncompressed && !is_zlib(chunk) {
+        chunk.to_vec()
+    } else {
+        let mut encoder = ZlibEncoder::new(Vec::new(), compression.into());
+        encoder.write_all(chunk)?;
+        encoder.finish()?
+    };
+
+    let mut block = Vec::with_capacity(BLOCK_HEADER_SIZE as usize + compressed.len());
+    block.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+    block.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+    block.extend_from_slice(&compressed);
+    Ok(block)
+}
+
+pub(crate) fn directory<'a>(filenames: impl ExactSizeIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut directory = Vec::new();
+    directory.extend_from_slice(&(filenames.len() as u32).to_le_bytes());
+    for name in filenames {
+        // Strings stored in directory are null terminated
+        directory.extend_from_slice(&(name.len() as u32 + 1).to_le_bytes());
+        directory.extend_from_slice(name);
+        directory.push(0);
+    }
+    directory
+}
+
+pub(crate) fn footer_timestamp(timestamp: SystemTime) -> u32 {
+    timestamp
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as u32)
+        .unwrap_or(0)
+}
@@ -0,0 +1,65 @@
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek, Write};
+use std::time::SystemTime;
+
+use time::OffsetDateTime;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+use crate::{Archive, ArchiveBuilder, Error};
+
+impl Archive<'_> {
+    /// Every file is stamped with the archive's timestamp, zip can't store
+    /// anything before 1980 so older or missing timestamps are left as the default
+    pub fn to_zip<W: Write + Seek>(&self, writer: W) -> Result<W, Error> {
+        let mut options =
+            SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        if let Some(modified) = self.timestamp().and_then(to_zip_time) {
+            options = options.last_modified_time(modified);
+        }
+
+        let mut zip = ZipWriter::new(writer);
+        for (filename, entry) in self.filenames.iter().zip(self.entries.iter()) {
+            zip.start_file(filename.as_str(), options)
+                .map_err(io::Error::from)?;
+            zip.write_all(&self.decompress(entry)?)?;
+        }
+        Ok(zip.finish().map_err(io::Error::from)?)
+    }
+
+    /// Directories are skipped, the archive takes the newest modification time
+    /// of the files in the zip as its timestamp
+    pub fn from_zip<R: Read + Seek>(reader: R) -> Result<Archive<'static>, Error> {
+        let mut zip = ZipArchive::new(reader).map_err(io::Error::from)?;
+        let mut builder = ArchiveBuilder::new();
+        let mut newest = None;
+
+        for position in 0..zip.len() {
+            let mut file = zip.by_index(position).map_err(io::Error::from)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            builder.add_file(file.name(), data);
+
+            let modified = file.last_modified().map(from_zip_time);
+            newest = newest.max(modified);
+        }
+        if let Some(newest) = newest.flatten() {
+            builder.set_timestamp(newest);
+        }
+
+        let mut buffer = Vec::new();
+        builder.write(&mut buffer)?;
+        Archive::from_bytes(buffer)
+    }
+}
+
+fn to_zip_time(time: SystemTime) -> Option<DateTime> {
+    DateTime::try_from(OffsetDateTime::from(time)).ok()
+}
+
+fn from_zip_time(time: DateTime) -> Option<SystemTime> {
+    OffsetDateTime::try_from(time).ok().map(SystemTime::from)
+}
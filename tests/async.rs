@@ -0,0 +1,52 @@
+#![cfg(feature = "async")]
+
+use futures::StreamExt;
+
+use eq_archive::{testing, Archive, Error, WriteOptions};
+
+#[tokio::test]
+async fn reads_archives_asynchronously() {
+    let files = testing::random_files(21, 3, 20_000);
+    let buffer = testing::build(&files, WriteOptions::default());
+    let path = std::env::temp_dir().join(format!("eq_archive_async_{}.s3d", std::process::id()));
+    std::fs::write(&path, &buffer).unwrap();
+    let archive = eq_archive::read_async(&path).await;
+    std::fs::remove_file(&path).unwrap();
+    let missing = eq_archive::read_async(&path).await;
+
+    assert_eq!(archive.unwrap().as_bytes(), &buffer[..]);
+    assert!(matches!(missing, Err(Error::IO(_))));
+}
+
+#[tokio::test]
+async fn streams_files_in_archive_order() {
+    let files = testing::random_files(22, 5, 20_000);
+    let archive = Archive::from_bytes(testing::build(&files, WriteOptions::default())).unwrap();
+
+    let streamed: Vec<_> = archive.files_stream().map(Result::unwrap).collect().await;
+    let expected: Vec<_> = archive
+        .filenames()
+        .iter()
+        .map(|name| (name.clone(), archive.get(name).unwrap().unwrap()))
+        .collect();
+    assert_eq!(streamed, expected);
+    assert_eq!(streamed.len(), files.len());
+}
+
+#[tokio::test]
+async fn streams_decompression_errors() {
+    let files = vec![("orc.bmp".to_string(), b"orc ".repeat(1000))];
+    let mut buffer = testing::build(&files, WriteOptions::default());
+    let archive = Archive::from_bytes(buffer.clone()).unwrap();
+    let offset = archive.entries[0].blocks()[0].offset();
+    buffer[offset + 2] ^= 0xff;
+    let archive = Archive::from_bytes(buffer).unwrap();
+
+    let mut stream = Box::pin(archive.files_stream());
+    match stream.next().await {
+        // Offsets are still from the start of the archive
+        Some(Err(Error::Decompress { offset: at, .. })) => assert_eq!(at, offset),
+        other => panic!("{:?}", other),
+    }
+    assert!(stream.next().await.is_none());
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "bevy")]
+
+use std::path::{Path, PathBuf};
+
+use bevy::asset::io::{AssetReader, AssetReaderError, Reader};
+use bevy::tasks::block_on;
+use bevy::tasks::futures_lite::StreamExt;
+
+use eq_archive::{testing, Archive, ArchiveAssetReader, WriteOptions};
+
+#[test]
+fn reads_files_as_assets() {
+    let files = testing::random_files(61, 4, 20_000);
+    let archive = Archive::from_bytes(testing::build(&files, WriteOptions::default())).unwrap();
+    let reader = ArchiveAssetReader::new(archive);
+
+    block_on(async {
+        for (name, data) in &files {
+            let mut file = reader.read(Path::new(name)).await.unwrap();
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer).await.unwrap();
+            assert_eq!(&buffer, data);
+        }
+
+        let missing = reader.read(Path::new("missing.bmp")).await.err();
+        assert_eq!(
+            missing,
+            Some(AssetReaderError::NotFound(PathBuf::from("missing.bmp")))
+        );
+        assert!(reader.read_meta(Path::new(&files[0].0)).await.is_err());
+    });
+}
+
+#[test]
+fn lists_the_root_as_the_only_directory() {
+    let files = testing::random_files(62, 3, 1_000);
+    let archive = Archive::from_bytes(testing::build(&files, WriteOptions::default())).unwrap();
+    let expected: Vec<_> = archive.filenames().iter().map(PathBuf::from).collect();
+    let reader = ArchiveAssetReader::new(archive);
+
+    block_on(async {
+        let listed: Vec<_> = reader
+            .read_directory(Path::new(""))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(listed, expected);
+        assert!(reader.is_directory(Path::new("")).await.unwrap());
+        assert!(!reader.is_directory(Path::new(&files[0].0)).await.unwrap());
+        assert!(reader.read_directory(Path::new("textures")).await.is_err());
+    });
+}
@@ -0,0 +1,368 @@
+use std::io::Write;
+
+use eq_archive::testing;
+use eq_archive::{
+    raw, Archive, ArchiveBuilder, Compression, Conflict, Error, ParseOptions, WriteOptions,
+};
+
+fn options(block_size: usize) -> WriteOptions {
+    WriteOptions {
+        block_size,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn merges_and_splits() {
+    let base = vec![
+        ("orc.bmp".to_string(), b"base".to_vec()),
+        ("orc.wld".to_string(), b"world".to_vec()),
+    ];
+    let patch = vec![("ORC.bmp".to_string(), b"patch".to_vec())];
+    let base = Archive::from_bytes(testing::build(&base, options(8192))).unwrap();
+    let patch = Archive::from_bytes(testing::build(&patch, options(8192))).unwrap();
+    let archives = [base, patch];
+
+    let merged = Archive::merge(&archives, Conflict::KeepLast).unwrap();
+    assert_eq!(merged.filenames(), ["ORC.bmp", "orc.wld"]);
+    assert_eq!(merged.get("orc.bmp").unwrap().unwrap(), b"patch");
+    let kept = Archive::merge(&archives, Conflict::KeepFirst).unwrap();
+    assert_eq!(kept.get("orc.bmp").unwrap().unwrap(), b"base");
+    assert!(Archive::merge(&archives, Conflict::Fail).is_err());
+
+    let (textures, rest) = merged.split(|name| name.ends_with(".bmp")).unwrap();
+    assert_eq!(textures.filenames(), ["ORC.bmp"]);
+    assert_eq!(rest.filenames(), ["orc.wld"]);
+}
+
+#[test]
+fn diffs_added_removed_and_changed_files() {
+    let old = vec![
+        ("orc.bmp".to_string(), b"orc ".repeat(1000)),
+        ("orc.wld".to_string(), b"world".to_vec()),
+        ("elf.bmp".to_string(), b"elf".to_vec()),
+        ("gnome.bmp".to_string(), b"gnome".to_vec()),
+    ];
+    // orc.bmp is the same but split into different blocks, gnome.bmp keeps
+    // its size so only its contents tell it apart
+    let new = vec![
+        ("ORC.bmp".to_string(), b"orc ".repeat(1000)),
+        ("orc.wld".to_string(), b"bigger world".to_vec()),
+        ("gnome.bmp".to_string(), b"GNOME".to_vec()),
+        ("dwarf.bmp".to_string(), b"dwarf".to_vec()),
+    ];
+    let old = Archive::from_bytes(testing::build(&old, options(8192))).unwrap();
+    let new = Archive::from_bytes(testing::build(&new, options(1024))).unwrap();
+
+    let diff = eq_archive::diff(&old, &new).unwrap();
+    assert_eq!(diff.added, ["dwarf.bmp"]);
+    assert_eq!(diff.removed, ["elf.bmp"]);
+    assert_eq!(diff.changed, ["orc.wld", "gnome.bmp"]);
+    assert!(!diff.is_empty());
+    assert!(eq_archive::diff(&new, &new).unwrap().is_empty());
+}
+
+#[test]
+fn repacks_smaller() {
+    let mut files = testing::random_files(5, 3, 20000);
+    files.push(("orc.txt".to_string(), b"orc ".repeat(5000)));
+    files.push(("orc2.txt".to_string(), b"orc ".repeat(5000)));
+    let stored = WriteOptions {
+        compression: Compression::Stored,
+        ..options(8192)
+    };
+    let mut archive = Archive::from_bytes(testing::build(&files, stored)).unwrap();
+
+    let report = archive.repack(&options(8192)).unwrap();
+    assert!(report.saved() > 0);
+    assert!(report.recompressed > 0);
+    assert_eq!(report.after, archive.as_bytes().len() as u64);
+    for (name, data) in &files {
+        assert_eq!(archive.get(name).unwrap().as_ref(), Some(data));
+    }
+    let strict = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    eq_archive::load_with(archive.as_bytes(), &strict).unwrap();
+}
+
+#[test]
+fn patches_in_place() {
+    let files = vec![
+        ("grass.bmp".to_string(), b"grass ".repeat(1000)),
+        ("tree.bmp".to_string(), b"tree ".repeat(1000)),
+    ];
+    let path = std::env::temp_dir().join(format!("eq_archive_patch_{}.s3d", std::process::id()));
+    std::fs::write(&path, testing::build(&files, options(8192))).unwrap();
+    let mut archive = eq_archive::read(&path).unwrap();
+    let timestamp = archive.timestamp().unwrap();
+    let defaults = WriteOptions::default();
+
+    let patched = b"grass".repeat(1000);
+    assert!(archive
+        .patch_in_place(&path, "grass.bmp", patched.clone(), &defaults)
+        .unwrap());
+    // Patched in memory the same way as on disk, with a new footer timestamp
+    assert_eq!(archive.as_bytes(), &std::fs::read(&path).unwrap()[..]);
+    assert!(archive.timestamp().unwrap() > timestamp);
+    let bigger = testing::random_files(7, 1, 10000).remove(0).1;
+    assert!(!archive
+        .patch_in_place(&path, "tree.bmp", bigger.clone(), &defaults)
+        .unwrap());
+
+    let reread = eq_archive::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    for archive in [&archive, &reread] {
+        assert_eq!(archive.get("grass.bmp").unwrap(), Some(patched.clone()));
+        assert_eq!(archive.get("tree.bmp").unwrap(), Some(bigger.clone()));
+    }
+}
+
+#[test]
+fn reloads_when_the_file_changes() {
+    let files = vec![("grass.bmp".to_string(), b"grass".to_vec())];
+    let path = std::env::temp_dir().join(format!("eq_archive_reload_{}.s3d", std::process::id()));
+    std::fs::write(&path, testing::build(&files, options(8192))).unwrap();
+    let latin1 = ParseOptions {
+        encoding: eq_archive::Encoding::Latin1,
+        ..Default::default()
+    };
+    let mut archive = eq_archive::read_with(path.to_str().unwrap(), &latin1).unwrap();
+    let unchanged = archive.reload_if_changed(&path).unwrap();
+
+    // Rewritten by something else
+    let mut builder = ArchiveBuilder::new();
+    builder
+        .add_file("grass.bmp", b"new grass".to_vec())
+        .add_file_raw(b"caf\xe9.bmp", b"cafe".to_vec());
+    std::fs::write(&path, builder.in_memory().unwrap()).unwrap();
+    let changed = archive.reload_if_changed(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let missing = archive.reload_if_changed(&path);
+
+    assert!(!unchanged);
+    assert!(changed);
+    assert_eq!(archive.get("grass.bmp").unwrap().unwrap(), b"new grass");
+    // Parsed again with the options it was first read with
+    assert_eq!(archive.get("café.bmp").unwrap().unwrap(), b"cafe");
+    assert!(matches!(missing, Err(Error::IO(_))));
+}
+
+#[test]
+fn reloads_after_another_handle_patches() {
+    let files = vec![("grass.bmp".to_string(), b"grass ".repeat(1000))];
+    let path = std::env::temp_dir().join(format!("eq_archive_handles_{}.s3d", std::process::id()));
+    std::fs::write(&path, testing::build(&files, options(8192))).unwrap();
+    let mut first = eq_archive::read(&path).unwrap();
+    let mut second = eq_archive::read(&path).unwrap();
+
+    // Same size, so only the blocks change on disk
+    let patched = b"GRASS ".repeat(1000);
+    assert!(first
+        .patch_in_place(
+            &path,
+            "grass.bmp",
+            patched.clone(),
+            &WriteOptions::default()
+        )
+        .unwrap());
+    let reloaded = second.reload_if_changed(&path).unwrap();
+    let unchanged = second.reload_if_changed(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(reloaded);
+    assert!(!unchanged);
+    assert_eq!(second.get("grass.bmp").unwrap(), Some(patched));
+}
+
+#[test]
+fn reload_checks_only_the_size_time_and_footer() {
+    let files = vec![("grass.bmp".to_string(), b"grass ".repeat(1000))];
+    let path = std::env::temp_dir().join(format!("eq_archive_stamp_{}.s3d", std::process::id()));
+    let buffer = testing::build(&files, options(8192));
+    std::fs::write(&path, &buffer).unwrap();
+    let mut archive = eq_archive::read(&path).unwrap();
+    let modified = std::fs::metadata(&path).unwrap().modified().unwrap();
+
+    // Scribbled over without changing the size, footer or modification time
+    let mut scribbled = buffer.clone();
+    scribbled[20] ^= 0xff;
+    let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+    (&file).write_all(&scribbled).unwrap();
+    file.set_modified(modified).unwrap();
+    let unnoticed = archive.reload_if_changed(&path).unwrap();
+
+    // From memory there's nothing to compare against without reading it
+    let mut from_bytes = Archive::from_bytes(scribbled).unwrap();
+    let compared = from_bytes.reload_if_changed(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(!unnoticed);
+    assert!(!compared);
+    assert_eq!(archive.as_bytes(), &buffer[..]);
+}
+
+#[test]
+fn renames_without_touching_blocks() {
+    let files = testing::random_files(12, 4, 20_000);
+    let mut archive = Archive::from_bytes(testing::build(&files, options(4096))).unwrap();
+    let blocks = |archive: &Archive| -> Vec<Vec<u8>> {
+        archive
+            .entries
+            .iter()
+            .map(|entry| {
+                let span = entry.data_span();
+                archive.as_bytes()[span.start as usize..span.end as usize].to_vec()
+            })
+            .collect()
+    };
+    let before = blocks(&archive);
+    let timestamp = archive.timestamp();
+    let old = archive.filenames()[1].clone();
+
+    archive.rename(&old, "renamed.wld").unwrap();
+    assert_eq!(blocks(&archive), before);
+    assert_eq!(archive.timestamp(), timestamp);
+    assert!(archive.get(&old).unwrap().is_none());
+    assert_eq!(archive.get("renamed.wld").unwrap().unwrap(), files[1].1);
+    assert_eq!(archive.filenames()[1], "renamed.wld");
+
+    // Only the case changes, which still needs a new CRC
+    archive.rename("renamed.wld", "RENAMED.wld").unwrap();
+    assert_eq!(archive.filenames()[1], "RENAMED.wld");
+    assert!(archive.verify().is_empty());
+    assert!(matches!(
+        archive.rename(&files[0].0, "RENAMED.WLD"),
+        Err(Error::FileExists(_))
+    ));
+}
+
+#[test]
+fn edits_by_appending() {
+    let files = testing::random_files(14, 3, 20_000);
+    let original = testing::build(&files, options(4096));
+    let mut archive = Archive::from_bytes(original.clone()).unwrap();
+    archive.set_edit_mode(eq_archive::EditMode::Append);
+    let table = raw::header(&original).unwrap().1.pointer() as usize;
+
+    archive.insert("added.wld", b"added".to_vec()).unwrap();
+    // The existing block data after the header is kept as it was
+    assert_eq!(archive.as_bytes()[12..table], original[12..table]);
+    let replaced = archive.replace(&files[0].0, b"replaced".to_vec()).unwrap();
+    assert_eq!(replaced, files[0].1);
+    let removed = archive.remove(&files[1].0).unwrap();
+    assert_eq!(removed, files[1].1);
+    assert_eq!(archive.as_bytes()[12..table], original[12..table]);
+
+    let reloaded = Archive::from_bytes(archive.as_bytes().to_vec()).unwrap();
+    assert!(reloaded.check().is_ok());
+    assert_eq!(reloaded.filenames().len(), 3);
+    assert_eq!(reloaded.get("added.wld").unwrap().unwrap(), b"added");
+    assert_eq!(reloaded.get(&files[0].0).unwrap().unwrap(), b"replaced");
+    assert!(reloaded.get(&files[1].0).unwrap().is_none());
+    assert_eq!(reloaded.get(&files[2].0).unwrap().unwrap(), files[2].1);
+}
+
+#[test]
+fn repairs_wrong_crcs() {
+    let files = vec![
+        ("orc.bmp".to_string(), b"orc ".repeat(1000)),
+        ("elf.bmp".to_string(), b"elf".to_vec()),
+        ("gnoll.wld".to_string(), b"gnoll".to_vec()),
+    ];
+    let mut buffer = testing::build(&files, options(1024));
+    // Give orc.bmp's entry a CRC that isn't its name's, as some packers did
+    let table = raw::header(&buffer).unwrap().1.pointer() as usize;
+    let crc = eq_archive::crc::filename_crc("orc.bmp").to_le_bytes();
+    let record = (table + 4..buffer.len())
+        .step_by(12)
+        .find(|&record| buffer[record..record + 4] == crc)
+        .unwrap();
+    buffer[record..record + 4].copy_from_slice(&0xdead_beefu32.to_le_bytes());
+
+    let mut archive = Archive::from_bytes(buffer).unwrap();
+    let mismatches = archive.verify();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].filename, "orc.bmp");
+    assert_eq!(mismatches[0].found, 0xdead_beef);
+
+    archive.repair().unwrap();
+    assert!(archive.verify().is_empty());
+    let reloaded = Archive::from_bytes(archive.as_bytes().to_vec()).unwrap();
+    assert!(reloaded.verify().is_empty());
+    for (name, data) in &files {
+        assert_eq!(reloaded.get(name).unwrap().as_ref(), Some(data));
+    }
+}
+
+#[test]
+fn keeps_unnamed_entries_out_of_the_directory() {
+    let mut builder = ArchiveBuilder::with_options(WriteOptions {
+        compression: Compression::Uncompressed,
+        ..Default::default()
+    });
+    builder
+        .add_file("orc.bmp", b"orc".to_vec())
+        .add_file("elf.bmp", b"elf".to_vec());
+    let mut buffer = builder.in_memory().unwrap();
+    // Only list the first name, leaving elf.bmp unnamed
+    let directory = eq_archive::load(&buffer)
+        .unwrap()
+        .directory_entry()
+        .unwrap()
+        .blocks()[0]
+        .offset();
+    buffer[directory..directory + 4].copy_from_slice(&1u32.to_le_bytes());
+    let unnamed = format!("crc_0x{:08X}.bin", eq_archive::crc::filename_crc("elf.bmp"));
+
+    // Each name in the directory is a length, the name and a null
+    let listed = |archive: &Archive, names: &[&str]| {
+        let size = 4 + names.iter().map(|name| 4 + name.len() + 1).sum::<usize>();
+        assert_eq!(archive.directory_entry().unwrap().size() as usize, size);
+        let mut filenames = archive.filenames().to_vec();
+        filenames.sort();
+        let mut expected: Vec<_> = names.iter().map(|name| name.to_string()).collect();
+        expected.push(unnamed.clone());
+        expected.sort();
+        assert_eq!(filenames, expected);
+        assert_eq!(archive.get(&unnamed).unwrap().unwrap(), b"elf");
+    };
+
+    for mode in [eq_archive::EditMode::Rebuild, eq_archive::EditMode::Append] {
+        let mut archive = Archive::from_bytes(buffer.clone()).unwrap();
+        assert_eq!(archive.get(&unnamed).unwrap().unwrap(), b"elf");
+        archive.set_edit_mode(mode);
+        archive.insert("troll.bmp", b"troll".to_vec()).unwrap();
+        listed(&archive, &["orc.bmp", "troll.bmp"]);
+        archive.rename("troll.bmp", "ogre.bmp").unwrap();
+        listed(&archive, &["orc.bmp", "ogre.bmp"]);
+    }
+
+    let archive = Archive::from_bytes(buffer).unwrap();
+    let (orcs, rest) = archive.split(|name| name.starts_with("orc")).unwrap();
+    listed(&rest, &[]);
+    let merged = Archive::merge(&[orcs, rest], Conflict::Fail).unwrap();
+    listed(&merged, &["orc.bmp"]);
+}
+
+#[test]
+fn stores_metadata() {
+    let mut builder = ArchiveBuilder::new();
+    builder.add_file("tree.mod", b"model".to_vec());
+    builder.add_metadata(b"{}".to_vec());
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer).unwrap();
+
+    let mut archive = eq_archive::load(&buffer).unwrap().into_owned();
+    let directory = archive.directory_entry().unwrap();
+    assert_eq!(directory.crc(), raw::DIRECTORY_CRC);
+    assert_eq!(archive.metadata().unwrap(), Some(b"{}".to_vec()));
+
+    archive.set_metadata(b"{\"packer\": 2}".to_vec()).unwrap();
+    assert_eq!(
+        archive.metadata().unwrap(),
+        Some(b"{\"packer\": 2}".to_vec())
+    );
+    assert_eq!(archive.filenames().len(), 2);
+}
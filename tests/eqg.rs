@@ -0,0 +1,152 @@
+use eq_archive::{ArchiveBuilder, Error, ParseOptions};
+
+// Size of the "STEVE" footer and its timestamp
+const FOOTER_SIZE: usize = 9;
+
+fn build(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = ArchiveBuilder::new();
+    for (name, data) in files {
+        builder.add_file(name, data.to_vec());
+    }
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer).unwrap();
+    buffer
+}
+
+fn eqg(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut buffer = build(files);
+    buffer.truncate(buffer.len() - FOOTER_SIZE);
+    buffer
+}
+
+#[test]
+fn reads_eqg_without_footer() {
+    let buffer = eqg(&[("gfaydark.ter", b"terrain"), ("gfaydark.zon", b"zone")]);
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    assert_eq!(
+        archive.filenames(),
+        vec!["gfaydark.ter".to_string(), "gfaydark.zon".to_string()]
+    );
+    assert_eq!(archive.get("gfaydark.zon").unwrap(), Some(b"zone".to_vec()));
+}
+
+#[test]
+fn reads_eqg_with_footer() {
+    let buffer = build(&[("tree.mod", b"model")]);
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    assert_eq!(archive.get("TREE.MOD").unwrap(), Some(b"model".to_vec()));
+}
+
+#[test]
+fn verifies_eqg_crcs() {
+    let buffer = eqg(&[("tree.mod", b"model")]);
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    assert!(archive.verify().is_empty());
+}
+
+#[test]
+fn ignores_partial_footer() {
+    let mut buffer = eqg(&[("tree.mod", b"model")]);
+    buffer.extend_from_slice(b"STE");
+
+    let archive = eq_archive::load(&buffer).unwrap();
+    assert_eq!(archive.filenames(), vec!["tree.mod".to_string()]);
+}
+
+#[test]
+fn strict_rejects_partial_footer() {
+    let options = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let mut buffer = eqg(&[("tree.mod", b"model")]);
+    assert!(eq_archive::load_with(&buffer, &options).is_ok());
+
+    buffer.extend_from_slice(b"STE");
+    assert!(matches!(
+        eq_archive::load_with(&buffer, &options),
+        Err(Error::TrailingData { .. })
+    ));
+}
+
+#[test]
+fn writes_eqg_without_footer() {
+    let files: &[(&str, &[u8])] = &[("orc.mod", b"orc"), ("orc.mds", b"mds")];
+    let write = |options| {
+        let mut builder = ArchiveBuilder::with_options(options);
+        for (name, data) in files {
+            builder.add_file(name, data.to_vec());
+        }
+        builder.in_memory().unwrap()
+    };
+    let retail = write(eq_archive::WriteOptions::retail());
+    let buffer = write(eq_archive::WriteOptions::eqg());
+    assert_eq!(buffer, retail[..retail.len() - FOOTER_SIZE]);
+
+    let mut archive = eq_archive::Archive::from_bytes(buffer).unwrap();
+    assert_eq!(archive.timestamp(), None);
+    assert_eq!(archive.get("orc.mds").unwrap().unwrap(), b"mds");
+    let last = archive.entries.iter().map(|entry| entry.pointer()).max();
+    assert!(archive.directory_entry().unwrap().pointer() > last.unwrap());
+
+    // Renaming doesn't add one
+    archive.rename("orc.mds", "elf.mds").unwrap();
+    assert_eq!(archive.timestamp(), None);
+
+    let mut writer = eq_archive::ArchiveWriter::with_options(
+        std::io::Cursor::new(Vec::new()),
+        eq_archive::WriteOptions::eqg(),
+    )
+    .unwrap();
+    writer.start_file("orc.mod").unwrap();
+    std::io::Write::write_all(&mut writer, b"orc").unwrap();
+    let streamed = writer.finish().unwrap().into_inner();
+    assert_eq!(eq_archive::load(&streamed).unwrap().timestamp(), None);
+}
+
+#[test]
+fn edits_and_repacks_eqg_without_adding_a_footer() {
+    let files = vec![
+        ("orc.mod".to_string(), vec![1; 5000]),
+        ("orc.mds".to_string(), b"mds".to_vec()),
+    ];
+    let buffer = eq_archive::testing::build(&files, eq_archive::WriteOptions::eqg());
+    for mode in [eq_archive::EditMode::Rebuild, eq_archive::EditMode::Append] {
+        let mut archive = eq_archive::Archive::from_bytes(buffer.clone()).unwrap();
+        archive.set_edit_mode(mode);
+        archive.insert("elf.mod", b"elf".to_vec()).unwrap();
+        archive.replace("orc.mds", b"new".to_vec()).unwrap();
+        archive.remove("orc.mod").unwrap();
+        assert_eq!(archive.timestamp(), None, "{:?}", mode);
+        assert_eq!(archive.get("orc.mds").unwrap().unwrap(), b"new");
+        assert!(archive.check().is_ok());
+    }
+
+    let mut archive = eq_archive::Archive::from_bytes(buffer.clone()).unwrap();
+    let report = archive.repack(&eq_archive::WriteOptions::eqg()).unwrap();
+    assert!(report.after <= report.before);
+    assert_eq!(report.saved(), report.before - report.after);
+    assert_eq!(archive.timestamp(), None);
+    let mut archive = eq_archive::Archive::from_bytes(buffer).unwrap();
+    archive
+        .repack(&eq_archive::WriteOptions::default())
+        .unwrap();
+    assert_eq!(archive.timestamp(), None);
+
+    // A footer is kept unless the options drop it
+    let s3d = eq_archive::testing::build(&files, eq_archive::WriteOptions::retail());
+    let mut archive = eq_archive::Archive::from_bytes(s3d.clone()).unwrap();
+    let timestamp = archive.timestamp();
+    archive.replace("orc.mds", b"new".to_vec()).unwrap();
+    assert!(archive.timestamp().is_some());
+    let mut archive = eq_archive::Archive::from_bytes(s3d.clone()).unwrap();
+    archive.repack(&eq_archive::WriteOptions::retail()).unwrap();
+    assert_eq!(archive.timestamp(), timestamp);
+    let mut archive = eq_archive::Archive::from_bytes(s3d).unwrap();
+    let report = archive.repack(&eq_archive::WriteOptions::eqg()).unwrap();
+    assert_eq!(archive.timestamp(), None);
+    assert!(report.saved() >= FOOTER_SIZE as u64);
+}
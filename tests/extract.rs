@@ -0,0 +1,126 @@
+use eq_archive::{Archive, ArchiveBuilder, Error};
+
+fn build(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = ArchiveBuilder::new();
+    for (name, data) in files {
+        builder.add_file(name, data.to_vec());
+    }
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer).unwrap();
+    buffer
+}
+
+#[test]
+fn refuses_to_extract_outside_the_destination() {
+    let dir = std::env::temp_dir().join(format!("eq_archive_unsafe_{}", std::process::id()));
+    let dest = dir.join("out");
+    let extract = |name: &str| {
+        let mut builder = ArchiveBuilder::new();
+        builder.add_file(name, b"orc".to_vec());
+        Archive::from_bytes(builder.in_memory().unwrap())
+            .unwrap()
+            .extract_all(&dest)
+    };
+    let unsafe_names = [
+        "../escape.bmp",
+        "textures/../../escape.bmp",
+        "..\\escape.bmp",
+        "textures\\..\\..\\escape.bmp",
+        "/escape.bmp",
+        "\\escape.bmp",
+        "C:\\escape.bmp",
+        "c:escape.bmp",
+        "",
+    ];
+    let results: Vec<_> = unsafe_names.iter().map(|name| extract(name)).collect();
+    // Backslashes are separators, not part of the name
+    let nested = extract("textures\\orc.bmp");
+    let written = std::fs::read(dest.join("textures").join("orc.bmp"));
+    let escaped = dir.join("escape.bmp").exists() || std::path::Path::new("/escape.bmp").exists();
+    std::fs::remove_dir_all(&dir).ok();
+
+    for (name, result) in unsafe_names.iter().zip(results) {
+        match result {
+            Err(Error::UnsafePath(filename)) => assert_eq!(&filename, name),
+            other => panic!("{:?} {:?}", name, other),
+        }
+    }
+    assert!(!escaped);
+    nested.unwrap();
+    assert_eq!(written.unwrap(), b"orc");
+}
+
+#[test]
+#[cfg(feature = "hash")]
+fn extracts_through_a_cache() {
+    use eq_archive::{CacheConfig, ExtractOptions};
+
+    let dir = std::env::temp_dir().join(format!("eq_archive_cache_{}", std::process::id()));
+    let options = ExtractOptions {
+        cache: Some(CacheConfig::new(dir.join("cache"), 10)),
+        ..Default::default()
+    };
+    let archive =
+        eq_archive::Archive::from_bytes(build(&[("orc.bmp", b"orc"), ("elf.bmp", b"elf")]))
+            .unwrap();
+    archive
+        .extract_all_with(&dir.join("first"), &options)
+        .unwrap();
+
+    // The second extraction copies what's cached rather than inflating again
+    let cached: Vec<_> = std::fs::read_dir(dir.join("cache")).unwrap().collect();
+    assert_eq!(cached.len(), 1);
+    let cached = cached[0].as_ref().unwrap().path();
+    assert_eq!(cached, dir.join("cache").join(archive.hash().to_string()));
+    std::fs::write(cached.join("orc.bmp"), b"ORC").unwrap();
+    archive
+        .extract_all_with(&dir.join("second"), &options)
+        .unwrap();
+    let orc = std::fs::read(dir.join("second").join("orc.bmp")).unwrap();
+
+    // Another archive pushes the first one out of a cache this small
+    let other = eq_archive::Archive::from_bytes(build(&[("gnoll.bmp", b"gnoll")])).unwrap();
+    other
+        .extract_all_with(&dir.join("third"), &options)
+        .unwrap();
+    let still_cached = cached.exists();
+    let entries = std::fs::read_dir(dir.join("cache")).unwrap().count();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(orc, b"ORC");
+    assert!(!still_cached);
+    assert_eq!(entries, 1);
+}
+
+#[test]
+fn serves_the_same_files_through_vfs() {
+    use eq_archive::{ArchiveSet, LooseFiles, Vfs};
+
+    fn assert_serves(vfs: &dyn Vfs) {
+        assert_eq!(vfs.list(), ["elf.bmp", "orc.bmp"]);
+        assert!(vfs.exists("ORC.bmp"));
+        assert!(!vfs.exists("gnoll.bmp"));
+        assert_eq!(vfs.read("Orc.BMP").unwrap(), b"orc");
+        assert!(matches!(vfs.open("gnoll.bmp"), Err(Error::FileNotFound(_))));
+    }
+
+    let buffer = build(&[("elf.bmp", b"elf"), ("orc.bmp", b"orc")]);
+    let archive = eq_archive::load(&buffer).unwrap();
+    assert_serves(&archive);
+
+    let mut set = ArchiveSet::new();
+    set.mount(
+        eq_archive::Archive::from_bytes(build(&[("elf.bmp", b"elf"), ("orc.bmp", b"old")]))
+            .unwrap(),
+    )
+    .mount(eq_archive::Archive::from_bytes(build(&[("orc.bmp", b"orc")])).unwrap());
+    assert_serves(&set);
+
+    let dir = std::env::temp_dir().join(format!("eq_archive_vfs_{}", std::process::id()));
+    archive.extract_all(&dir).unwrap();
+    let loose = LooseFiles::new(&dir);
+    let escaped = loose.open("../orc.bmp").err();
+    assert_serves(&loose);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(matches!(escaped, Some(Error::UnsafePath(_))));
+}
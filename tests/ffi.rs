@@ -0,0 +1,52 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::{CStr, CString};
+
+use eq_archive::ffi::{
+    eq_archive_extract_all, eq_archive_filenames, eq_archive_free, eq_archive_free_data,
+    eq_archive_get, eq_archive_open,
+};
+use eq_archive::{testing, WriteOptions};
+
+#[test]
+fn reads_through_the_c_bindings() {
+    let files = vec![
+        ("orc.bmp".to_string(), b"orc ".repeat(1000)),
+        ("elf.wld".to_string(), b"elf".to_vec()),
+    ];
+    let dir = std::env::temp_dir().join(format!("eq_archive_ffi_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("orc.s3d");
+    std::fs::write(&path, testing::build(&files, WriteOptions::default())).unwrap();
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    let c_dest = CString::new(dir.join("out").to_str().unwrap()).unwrap();
+
+    unsafe {
+        let archive = eq_archive_open(c_path.as_ptr());
+        assert!(!archive.is_null());
+
+        let mut count = 0;
+        let names = eq_archive_filenames(archive, &mut count);
+        let names: Vec<_> = (0..count)
+            .map(|i| CStr::from_ptr(*names.add(i)).to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["orc.bmp", "elf.wld"]);
+
+        let name = CString::new("ORC.BMP").unwrap();
+        let mut len = 0;
+        let data = eq_archive_get(archive, name.as_ptr(), &mut len);
+        assert_eq!(std::slice::from_raw_parts(data, len), &files[0].1[..]);
+        eq_archive_free_data(data, len);
+        let missing = CString::new("gnoll.bmp").unwrap();
+        assert!(eq_archive_get(archive, missing.as_ptr(), &mut len).is_null());
+
+        assert_eq!(eq_archive_extract_all(archive, c_dest.as_ptr()), 0);
+        eq_archive_free(archive);
+
+        let unreadable = CString::new(dir.join("missing.s3d").to_str().unwrap()).unwrap();
+        assert!(eq_archive_open(unreadable.as_ptr()).is_null());
+    }
+    let extracted = std::fs::read(dir.join("out").join("elf.wld"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(extracted.unwrap(), b"elf");
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "hash")]
+
+#[test]
+fn hashes_whole_archives() {
+    let dir = std::env::temp_dir().join(format!("eq_archive_hash_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let vectors: [(&str, Vec<u8>, &str); 3] = [
+        (
+            "abc",
+            b"abc".to_vec(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            "two_blocks",
+            b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq".to_vec(),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+        (
+            "million",
+            vec![b'a'; 1_000_000],
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+        ),
+    ];
+    let mut hashes = Vec::new();
+    for (name, data, _) in &vectors {
+        std::fs::write(dir.join(name), data).unwrap();
+        hashes.push(eq_archive::hash_file(dir.join(name)).unwrap());
+    }
+    let mut builder = eq_archive::ArchiveBuilder::new();
+    builder
+        .add_file("orc.bmp", b"orc".to_vec())
+        .set_timestamp(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000));
+    builder.write_to(dir.join("orc.s3d")).unwrap();
+    let file_hash = eq_archive::hash_file(dir.join("orc.s3d")).unwrap();
+    let archive = eq_archive::read(dir.join("orc.s3d")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    for (hash, (_, _, expected)) in hashes.iter().zip(vectors.iter()) {
+        assert_eq!(hash.to_string(), *expected);
+        assert_eq!(hash.timestamp, None);
+    }
+    assert_eq!(file_hash.timestamp, Some(1_000_000));
+    assert_eq!(archive.hash(), file_hash);
+    assert_eq!(archive.manifest().unwrap().archive, Some(file_hash));
+}
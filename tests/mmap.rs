@@ -0,0 +1,28 @@
+#![cfg(feature = "mmap")]
+
+use eq_archive::{testing, Error, WriteOptions};
+
+#[test]
+fn reads_memory_mapped_archives() {
+    let files = testing::random_files(24, 4, 20_000);
+    let buffer = testing::build(&files, WriteOptions::default());
+    let path = std::env::temp_dir().join(format!("eq_archive_mmap_{}.s3d", std::process::id()));
+    std::fs::write(&path, &buffer).unwrap();
+
+    let mut archive = eq_archive::open_mmap(&path).unwrap();
+    assert_eq!(archive.as_bytes(), &buffer[..]);
+    for (name, data) in &files {
+        assert_eq!(archive.get(name).unwrap().as_ref(), Some(data));
+    }
+    // The mapped file is stamped like one read into memory
+    assert!(!archive.reload_if_changed(&path).unwrap());
+    // Unmapped before the file is truncated under it
+    drop(archive);
+
+    std::fs::write(&path, b"not an archive").unwrap();
+    let invalid = eq_archive::open_mmap(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(invalid.is_err());
+    let missing = eq_archive::open_mmap(&path);
+    assert!(matches!(missing, Err(Error::IO(_))));
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "parallel")]
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+use eq_archive::{testing, Archive, Error, ProgressEvent, WriteOptions};
+
+#[test]
+fn compresses_files_in_parallel() {
+    // Progress is reported from whichever thread compressed the file
+    static THREADS: Mutex<Option<HashSet<ThreadId>>> = Mutex::new(None);
+    fn record(_: ProgressEvent) {
+        THREADS
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashSet::new)
+            .insert(thread::current().id());
+    }
+
+    let files = testing::random_files(31, 32, 200_000);
+    let threaded = WriteOptions {
+        threads: 4,
+        progress: Some(record),
+        ..Default::default()
+    };
+    let multi = testing::without_footer(testing::build(&files, threaded));
+    let threads = THREADS.lock().unwrap().take().unwrap();
+    let single = testing::without_footer(testing::build(&files, WriteOptions::default()));
+
+    assert!(threads.len() > 1, "{:?}", threads);
+    assert!(!threads.contains(&thread::current().id()));
+    assert_eq!(multi, single);
+}
+
+#[test]
+fn decompresses_files_in_parallel() {
+    let files = testing::random_files(32, 16, 50_000);
+    let buffer = testing::build(&files, WriteOptions::default());
+    let archive = Archive::from_bytes(buffer.clone()).unwrap();
+    let expected: Vec<_> = archive.iter_files().map(Result::unwrap).collect();
+    let offset = archive.entries[3].blocks()[0].offset();
+
+    // Files come back in archive order whichever thread inflated them
+    let decompressed: Vec<_> = archive.files_par().unwrap().collect();
+    assert_eq!(decompressed, expected);
+    assert_eq!(decompressed.len(), files.len());
+
+    let mut corrupt = buffer;
+    corrupt[offset + 2] ^= 0xff;
+    let archive = Archive::from_bytes(corrupt).unwrap();
+    match archive.files_par().map(|_| ()) {
+        Err(Error::Decompress { offset: at, .. }) => assert_eq!(at, offset),
+        other => panic!("{:?}", other),
+    }
+}
@@ -0,0 +1,643 @@
+use std::convert::TryInto;
+
+use eq_archive::testing;
+use eq_archive::{raw, Archive, ArchiveBuilder, Error, LazyArchive, ParseOptions, WriteOptions};
+
+fn build(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = ArchiveBuilder::new();
+    for (name, data) in files {
+        builder.add_file(name, data.to_vec());
+    }
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer).unwrap();
+    buffer
+}
+
+fn options(block_size: usize) -> WriteOptions {
+    WriteOptions {
+        block_size,
+        ..Default::default()
+    }
+}
+
+// Size of the "STEVE" footer and its timestamp
+const FOOTER_SIZE: usize = 9;
+
+#[test]
+fn decodes_latin1_filenames() {
+    let raw_name = b"caf\xe9.bmp".to_vec();
+    let mut builder = ArchiveBuilder::new();
+    builder
+        .add_file_raw(&raw_name, b"cafe".to_vec())
+        .add_file("orc.bmp", b"orc".to_vec());
+    let buffer = builder.in_memory().unwrap();
+
+    let archive = eq_archive::load(&buffer).unwrap();
+    assert_eq!(archive.filenames()[0], "caf\u{FFFD}.bmp");
+    assert_eq!(archive.raw_filenames()[0], raw_name);
+    // CRCs are of the stored bytes, not the decoded name
+    assert!(archive.verify().is_empty());
+
+    let latin1 = ParseOptions {
+        encoding: eq_archive::Encoding::Latin1,
+        ..Default::default()
+    };
+    let mut archive = eq_archive::load_with(&buffer, &latin1).unwrap();
+    assert_eq!(archive.filenames()[0], "café.bmp");
+    // Only ASCII letters are case insensitive
+    assert_eq!(archive.get("CAFé.bmp").unwrap().unwrap(), b"cafe");
+
+    // Edits write the name back out as it was read
+    archive.insert("elf.bmp", b"elf".to_vec()).unwrap();
+    assert!(archive.raw_filenames().contains(&raw_name));
+    assert_eq!(archive.get("café.bmp").unwrap().unwrap(), b"cafe");
+    assert!(archive.verify().is_empty());
+}
+
+#[test]
+fn rejects_files_that_arent_archives() {
+    let buffer = testing::build(&[("orc.bmp".to_string(), b"orc".to_vec())], options(8192));
+    assert!(eq_archive::sniff(&buffer));
+    assert_eq!(
+        u32::from_le_bytes(buffer[4..8].try_into().unwrap()),
+        eq_archive::PFS_MAGIC
+    );
+
+    let mut bitmap = buffer.clone();
+    bitmap[4..8].copy_from_slice(b"BM6\0");
+    assert!(!eq_archive::sniff(&bitmap));
+    assert!(!eq_archive::sniff(b"PFS"));
+    match eq_archive::load(&bitmap) {
+        Err(Error::NotAPfsArchive { found_magic }) => {
+            assert_eq!(found_magic, u32::from_le_bytes(*b"BM6\0"))
+        }
+        other => panic!("{:?}", other.map(|archive| archive.filenames().to_vec())),
+    }
+
+    let path = std::env::temp_dir().join(format!("eq_archive_sniff_{}.s3d", std::process::id()));
+    std::fs::write(&path, &bitmap).unwrap();
+    let sniffed = eq_archive::sniff_file(path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(!sniffed);
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    let buffer = testing::build(&[("orc.bmp".to_string(), b"orc".to_vec())], options(8192));
+    let with_version = |version: u32| {
+        let mut buffer = buffer.clone();
+        buffer[8..12].copy_from_slice(&version.to_le_bytes());
+        buffer
+    };
+
+    for version in eq_archive::SUPPORTED_VERSIONS {
+        let archive = Archive::from_bytes(with_version(version)).unwrap();
+        assert_eq!(archive.get("orc.bmp").unwrap().unwrap(), b"orc");
+    }
+    match Archive::from_bytes(with_version(0x0003_0000)) {
+        Err(Error::UnsupportedVersion { version }) => assert_eq!(version, 0x0003_0000),
+        other => panic!("{:?}", other.map(|archive| archive.filenames().to_vec())),
+    }
+}
+
+#[test]
+fn reports_npak_containers() {
+    let mut npak = b"NPAK".to_vec();
+    npak.extend_from_slice(&[0; 60]);
+    assert_eq!(
+        eq_archive::Format::detect(&npak),
+        Some(eq_archive::Format::T3d)
+    );
+    assert!(!eq_archive::sniff(&npak));
+    assert!(matches!(
+        eq_archive::load(&npak),
+        Err(Error::UnsupportedFormat(eq_archive::Format::T3d))
+    ));
+
+    let buffer = testing::build(&[("orc.bmp".to_string(), b"orc".to_vec())], options(8192));
+    assert_eq!(
+        eq_archive::Format::detect(&buffer),
+        Some(eq_archive::Format::Pfs)
+    );
+    assert_eq!(eq_archive::Format::detect(b"orc"), None);
+}
+
+#[test]
+fn lazy_archive_reads_the_same_files() {
+    let files = testing::edge_case_files(4096);
+    let buffer = testing::build(&files, options(4096));
+    let archive = eq_archive::LazyArchive::open(buffer.as_slice()).unwrap();
+
+    for (name, data) in &files {
+        assert_eq!(archive.get(name).unwrap().as_ref(), Some(data));
+    }
+}
+
+#[test]
+fn lazy_archive_reads_deduplicated_files() {
+    let texture = testing::random_files(3, 1, 20_000).remove(0).1;
+    let files = vec![
+        ("orc.bmp".to_string(), texture.clone()),
+        ("elf.bmp".to_string(), b"elf".to_vec()),
+        ("orc2.bmp".to_string(), texture),
+    ];
+    let buffer = testing::build(&files, options(4096));
+    let archive = eq_archive::load(&buffer).unwrap();
+    assert_eq!(archive.entries[0].pointer(), archive.entries[1].pointer());
+
+    let lazy = LazyArchive::open(buffer.as_slice()).unwrap();
+    for (name, data) in &files {
+        assert_eq!(lazy.get(name).unwrap().as_ref(), Some(data));
+    }
+}
+
+#[test]
+fn raw_parsers_read_the_footer() {
+    let with_footer = build(&[("tree.mod", b"model")]);
+    let without_footer = testing::without_footer(build(&[("tree.mod", b"model")]));
+
+    for (buffer, has_footer) in [(with_footer, true), (without_footer, false)] {
+        let (_, header) = raw::header(&buffer).unwrap();
+        assert_eq!(header.magic_number(), raw::PFS_MAGIC);
+        let (rest, (count, entries, footer)) =
+            raw::entry_table(&buffer[header.pointer() as usize..]).unwrap();
+        assert_eq!(count, 2);
+        assert!(entries
+            .iter()
+            .any(|entry| entry.crc() == raw::DIRECTORY_CRC));
+        assert_eq!(footer.is_some(), has_footer);
+        assert!(rest.is_empty());
+    }
+}
+
+#[test]
+fn reads_concatenated_archives() {
+    let mut buffer = build(&[("gfaydark.wld", b"world")]);
+    buffer.extend(testing::without_footer(build(&[(
+        "gfaydark_obj.wld",
+        b"objects",
+    )])));
+    buffer.extend(build(&[("gfaydark_chr.wld", b"characters")]));
+
+    let archives = eq_archive::load_all(&buffer).unwrap();
+    let filenames: Vec<_> = archives
+        .iter()
+        .map(|archive| archive.filenames()[0].as_str())
+        .collect();
+    assert_eq!(
+        filenames,
+        ["gfaydark.wld", "gfaydark_obj.wld", "gfaydark_chr.wld"]
+    );
+    assert_eq!(
+        archives[2].get("gfaydark_chr.wld").unwrap(),
+        Some(b"characters".to_vec())
+    );
+}
+
+#[test]
+fn looks_up_names_exactly() {
+    let buffer = build(&[("Tree.mod", b"model"), ("tree.MOD", b"other")]);
+    let options = ParseOptions {
+        lookup: eq_archive::LookupOptions {
+            case_sensitive: true,
+        },
+        ..Default::default()
+    };
+    let mut archive = eq_archive::load_with(&buffer, &options).unwrap();
+
+    assert_eq!(archive.filenames(), ["Tree.mod", "tree.MOD"]);
+    assert_eq!(archive.get("tree.MOD").unwrap(), Some(b"other".to_vec()));
+    assert_eq!(archive.get("tree.mod").unwrap(), None);
+
+    archive.set_lookup(Default::default());
+    assert_eq!(archive.get("tree.mod").unwrap(), Some(b"model".to_vec()));
+    assert_eq!(eq_archive::normalize_filename("Tree.MOD"), "tree.mod");
+}
+
+#[test]
+fn enforces_limits() {
+    let buffer = build(&[("tree.mod", b"model"), ("tree2.mod", b"model 2")]);
+    let limited = |limits| {
+        let options = ParseOptions {
+            limits,
+            ..Default::default()
+        };
+        match eq_archive::load_with(&buffer, &options) {
+            Err(Error::LimitExceeded { limit, .. }) => Some(limit),
+            Err(e) => panic!("{}", e),
+            Ok(_) => None,
+        }
+    };
+    let defaults = eq_archive::Limits::default();
+
+    assert_eq!(limited(defaults), None);
+    let max_entries = eq_archive::Limits {
+        max_entries: 2,
+        ..defaults
+    };
+    assert_eq!(limited(max_entries), Some("max_entries"));
+    let max_file_size = eq_archive::Limits {
+        max_file_size: 5,
+        ..defaults
+    };
+    assert_eq!(limited(max_file_size), Some("max_file_size"));
+    let max_blocks = eq_archive::Limits {
+        max_blocks: 0,
+        ..defaults
+    };
+    assert_eq!(limited(max_blocks), Some("max_blocks"));
+}
+
+#[test]
+fn reports_integrity() {
+    let mut buffer = build(&[("tree.mod", b"model")]);
+    let archive = eq_archive::load(&buffer).unwrap();
+    assert!(archive.check().issues.is_empty());
+
+    buffer.extend_from_slice(b"junk");
+    let archive = eq_archive::load(&buffer).unwrap();
+    let report = archive.check();
+    assert!(report.is_ok());
+    assert_eq!(report.issues.len(), 1);
+    assert_eq!(report.issues[0].severity, eq_archive::Severity::Warning);
+    assert!(matches!(report.issues[0].error, Error::TrailingData { .. }));
+}
+
+#[test]
+fn validates_and_normalizes_names() {
+    use eq_archive::names::{normalize, validate};
+
+    assert!(validate("gfaydark.wld").is_ok());
+    assert!(validate("eq_archive_metadata.json").is_ok());
+    for name in [
+        "",
+        "Grass.bmp",
+        "textures/grass.bmp",
+        "grass.bmp\0",
+        "grass",
+        ".bmp",
+        "grass.bitmap",
+        "gr?ss.bmp",
+        "gräss.bmp",
+    ] {
+        assert!(
+            matches!(validate(name), Err(Error::InvalidFilename { .. })),
+            "{:?}",
+            name
+        );
+    }
+    assert!(validate(&"a".repeat(300)).is_err());
+
+    assert_eq!(normalize("Textures\\Grass.BMP").unwrap(), "grass.bmp");
+    assert_eq!(normalize(" orc.bmp\0\0").unwrap(), "orc.bmp");
+    match normalize("Textures/README") {
+        Err(Error::InvalidFilename { filename, .. }) => assert_eq!(filename, "Textures/README"),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn salvages_names_from_a_corrupt_directory() {
+    let mut builder = ArchiveBuilder::with_options(eq_archive::WriteOptions {
+        compression: eq_archive::Compression::Uncompressed,
+        ..Default::default()
+    });
+    builder
+        .add_file("orc.bmp", b"orc".to_vec())
+        .add_file("elf.bmp", b"elf".to_vec())
+        .add_file("gnoll.bmp", b"gnoll".to_vec());
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer).unwrap();
+
+    // Break the length of the second name so it runs past the end
+    let archive = eq_archive::load(&buffer).unwrap();
+    let second = archive.directory_entry().unwrap().blocks()[0].offset() + 4 + 4 + 8;
+    buffer[second..second + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    match eq_archive::load(&buffer) {
+        Err(Error::CorruptDirectory {
+            offset: 16,
+            names: 1,
+        }) => {}
+        other => panic!("{:?}", other.map(|archive| archive.filenames().to_vec())),
+    }
+    let options = ParseOptions {
+        recover: true,
+        ..Default::default()
+    };
+    let archive = eq_archive::load_with(&buffer, &options).unwrap();
+    let mut filenames = archive.filenames().to_vec();
+    filenames.sort();
+    assert_eq!(
+        filenames,
+        [
+            format!("crc_0x{:08X}.bin", eq_archive::crc::filename_crc("elf.bmp")),
+            format!(
+                "crc_0x{:08X}.bin",
+                eq_archive::crc::filename_crc("gnoll.bmp")
+            ),
+            "orc.bmp".to_string(),
+        ]
+    );
+    assert_eq!(archive.get("orc.bmp").unwrap().unwrap(), b"orc");
+    assert!(archive.verify().is_empty());
+    assert!(matches!(
+        archive.failures()[0].error,
+        Error::CorruptDirectory { names: 1, .. }
+    ));
+}
+
+#[test]
+fn iterates_in_directory_or_disk_order() {
+    let mut builder = ArchiveBuilder::with_options(eq_archive::WriteOptions {
+        compression: eq_archive::Compression::Uncompressed,
+        ..Default::default()
+    });
+    builder
+        .add_file("orc.bmp", b"orc".to_vec())
+        .add_file("elf.bmp", b"elf".to_vec())
+        .add_file("gnoll.bmp", b"gnoll".to_vec());
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer).unwrap();
+
+    // List the second file first in the directory, after the first in the data
+    let archive = eq_archive::load(&buffer).unwrap();
+    let names = archive.directory_entry().unwrap().blocks()[0].offset() + 4;
+    buffer[names + 4..names + 11].copy_from_slice(b"elf.bmp");
+    buffer[names + 16..names + 23].copy_from_slice(b"orc.bmp");
+
+    let archive = eq_archive::load(&buffer).unwrap();
+    let disk: Vec<_> = archive.iter_disk_order().map(Result::unwrap).collect();
+    let listed: Vec<_> = archive
+        .iter_directory_order()
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    let disk: Vec<_> = disk
+        .iter()
+        .map(|(name, data)| (name.as_str(), data.as_slice()))
+        .collect();
+    let listed: Vec<_> = listed
+        .iter()
+        .map(|(name, data)| (name.as_str(), data.as_slice()))
+        .collect();
+    assert_eq!(
+        disk,
+        [
+            ("orc.bmp", &b"orc"[..]),
+            ("elf.bmp", b"elf"),
+            ("gnoll.bmp", b"gnoll")
+        ]
+    );
+    assert_eq!(
+        listed,
+        [
+            ("elf.bmp", &b"elf"[..]),
+            ("orc.bmp", b"orc"),
+            ("gnoll.bmp", b"gnoll")
+        ]
+    );
+}
+
+// Lays the archive's blocks out again with the directory's at `position`
+// among the files, writing it a second time at the end when `stale`, like a
+// patcher that appended a new directory and kept the old entry
+fn move_directory(buffer: &[u8], position: usize, stale: bool) -> Vec<u8> {
+    let archive = eq_archive::load(buffer).unwrap();
+    let mut entries: Vec<_> = archive.entries.iter().collect();
+    entries.insert(position, archive.directory_entry().unwrap());
+    if stale {
+        entries.push(archive.directory_entry().unwrap());
+    }
+
+    let mut data = Vec::new();
+    let mut index = Vec::new();
+    for entry in entries {
+        let span = entry.data_span();
+        index.push((entry.crc(), 12 + data.len() as u32, entry.size()));
+        data.extend_from_slice(&buffer[span.start as usize..span.end as usize]);
+    }
+    index.sort_by_key(|&(crc, _, _)| crc);
+
+    let mut out = buffer[..12].to_vec();
+    out[..4].copy_from_slice(&(12 + data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&data);
+    out.extend_from_slice(&(index.len() as u32).to_le_bytes());
+    for (crc, pointer, size) in index {
+        for value in [crc, pointer, size] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    out.extend_from_slice(&buffer[buffer.len() - FOOTER_SIZE..]);
+    out
+}
+
+#[test]
+fn never_lists_the_directory_as_a_file() {
+    let files: &[(&str, &[u8])] = &[("orc.bmp", b"orc"), ("elf.bmp", b"elf"), ("gnoll.bmp", b"")];
+    let buffer = build(files);
+    for (position, stale) in [(0, false), (1, false), (3, false), (0, true)] {
+        let buffer = move_directory(&buffer, position, stale);
+        let archive = eq_archive::load(&buffer).unwrap();
+        assert_eq!(archive.filenames().len(), 3, "{} {}", position, stale);
+        assert!(archive.check().is_ok(), "{} {}", position, stale);
+        assert!(archive
+            .entries_info()
+            .iter()
+            .all(|info| info.crc != raw::DIRECTORY_CRC));
+        assert_eq!(archive.iter_disk_order().count(), 3);
+        let mut names: Vec<_> = archive.files().unwrap().map(|(name, _)| name).collect();
+        names.sort();
+        assert_eq!(names, ["elf.bmp", "gnoll.bmp", "orc.bmp"]);
+
+        let lazy = eq_archive::LazyArchive::open(buffer.as_slice()).unwrap();
+        assert_eq!(lazy.filenames().len(), 3);
+    }
+}
+
+#[test]
+fn fingerprints_change_with_the_archive() {
+    let mut builder = ArchiveBuilder::new();
+    builder
+        .add_file("orc.bmp", b"orc".to_vec())
+        .add_file("elf.bmp", b"elf".to_vec());
+    let archive = eq_archive::Archive::from_bytes(builder.in_memory().unwrap()).unwrap();
+    let fingerprint = archive.fingerprint();
+    assert_eq!(fingerprint.entry_count, 2);
+    assert_eq!(
+        fingerprint.timestamp,
+        Some(eq_archive::DETERMINISTIC_TIMESTAMP)
+    );
+    let again = eq_archive::Archive::from_bytes(builder.in_memory().unwrap()).unwrap();
+    assert_eq!(again.fingerprint(), fingerprint);
+
+    let mut renamed = again;
+    renamed.rename("elf.bmp", "wolf.bmp").unwrap();
+    assert_ne!(renamed.fingerprint(), fingerprint);
+
+    builder.add_file("gnoll.bmp", b"gnoll".to_vec());
+    let added = eq_archive::Archive::from_bytes(builder.in_memory().unwrap()).unwrap();
+    assert_eq!(added.fingerprint().entry_count, 3);
+
+    builder.set_timestamp(std::time::UNIX_EPOCH);
+    let restamped = eq_archive::Archive::from_bytes(builder.in_memory().unwrap()).unwrap();
+    assert_ne!(restamped.fingerprint(), added.fingerprint());
+}
+
+#[test]
+fn locates_parse_errors() {
+    let buffer = build(&[("orc.bmp", b"orc")]);
+    let pointer = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
+
+    let mut past_the_end = buffer.clone();
+    past_the_end[..4].copy_from_slice(&u32::MAX.to_le_bytes());
+    match eq_archive::load(&past_the_end) {
+        Err(Error::Parser {
+            section: eq_archive::Section::Header,
+            offset: 0,
+            before,
+            after,
+            ..
+        }) => {
+            assert!(before.is_empty());
+            assert_eq!(after, past_the_end[..16]);
+        }
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+
+    // An entry count far larger than the table
+    let mut too_many = buffer.clone();
+    too_many[pointer..pointer + 4].copy_from_slice(&1000u32.to_le_bytes());
+    let error = eq_archive::load(&too_many).map(|_| ()).unwrap_err();
+    match &error {
+        Error::Parser {
+            section: eq_archive::Section::EntryTable,
+            offset,
+            before,
+            after,
+            ..
+        } => {
+            assert_eq!(*offset, pointer + 4);
+            assert_eq!(before[before.len() - 4..], 1000u32.to_le_bytes());
+            assert_eq!(after, &too_many[pointer + 4..pointer + 20]);
+        }
+        other => panic!("{:?}", other),
+    }
+    assert!(error.to_string().starts_with(&format!(
+        "malformed entry table at offset {:#x}",
+        pointer + 4
+    )));
+}
+
+#[test]
+fn reads_archives_from_any_reader() {
+    use std::io::{self, Read};
+
+    // Hands out a few bytes at a time, then fails if asked to
+    struct Trickle<'a>(&'a [u8], bool);
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() && self.1 {
+                return Err(io::Error::other("disconnected"));
+            }
+            let len = buf.len().min(self.0.len()).min(7);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    let files = testing::random_files(25, 3, 5000);
+    let buffer = testing::build(&files, options(1024));
+    let archive = eq_archive::from_reader(Trickle(&buffer, false)).unwrap();
+    assert_eq!(archive.as_bytes(), &buffer[..]);
+    for (name, data) in &files {
+        assert_eq!(archive.get(name).unwrap().as_ref(), Some(data));
+    }
+
+    let failed = eq_archive::from_reader(Trickle(&buffer, true));
+    assert!(matches!(failed, Err(Error::IO(_))));
+    let mut trailing = buffer.clone();
+    trailing.extend_from_slice(b"junk");
+    let strict = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    assert!(eq_archive::from_reader(trailing.as_slice()).is_ok());
+    assert!(matches!(
+        eq_archive::from_reader_with(trailing.as_slice(), &strict),
+        Err(Error::TrailingData { .. })
+    ));
+}
+
+#[test]
+fn rejects_truncated_and_corrupt_blocks() {
+    let files = vec![("orc.bmp".to_string(), b"orc ".repeat(1000))];
+    let buffer = testing::build(&files, options(1024));
+    let archive = eq_archive::load(&buffer).unwrap();
+    // Each block header is its compressed size then its uncompressed size
+    let headers: Vec<_> = archive.entries[0]
+        .blocks()
+        .iter()
+        .map(|block| block.offset() - 8)
+        .collect();
+    assert_eq!(headers.len(), 4);
+
+    // A block running past the end of the archive
+    let mut truncated = buffer.clone();
+    truncated[headers[1]..headers[1] + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    match eq_archive::load(&truncated) {
+        Err(Error::TruncatedBlock { offset }) => assert_eq!(offset, headers[1]),
+        other => panic!("{:?}", other.map(|_| ())),
+    }
+
+    // A block inflating to more than is left of the file, and one inflating
+    // to nothing that would never finish it
+    for size in [5000u32, 0] {
+        let mut corrupt = buffer.clone();
+        corrupt[headers[0] + 4..headers[0] + 8].copy_from_slice(&size.to_le_bytes());
+        match eq_archive::load(&corrupt) {
+            Err(Error::CorruptBlock { offset }) => assert_eq!(offset, headers[0]),
+            other => panic!("{} {:?}", size, other.map(|_| ())),
+        }
+    }
+}
+
+#[test]
+fn peeks_at_archives_on_disk() {
+    let dir = std::env::temp_dir().join(format!("eq_archive_peek_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let s3d = dir.join("gfaydark.s3d");
+    let eqg_path = dir.join("orc.eqg");
+    let mut builder = ArchiveBuilder::new();
+    builder
+        .add_file("gfaydark.wld", vec![7; 20_000])
+        .add_file("readme.txt", Vec::new())
+        .add_file("tree.bmp", vec![1; 300]);
+    std::fs::write(&s3d, builder.in_memory().unwrap()).unwrap();
+    std::fs::write(
+        &eqg_path,
+        testing::without_footer(build(&[("orc.mod", b"orc")])),
+    )
+    .unwrap();
+
+    let peeked = eq_archive::peek(&s3d);
+    let peeked_eqg = eq_archive::peek(&eqg_path);
+    let missing = eq_archive::peek(dir.join("missing.s3d"));
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let peeked = peeked.unwrap();
+    assert_eq!(
+        peeked.files,
+        [
+            ("gfaydark.wld".to_string(), 20_000),
+            ("readme.txt".to_string(), 0),
+            ("tree.bmp".to_string(), 300)
+        ]
+    );
+    assert_eq!(peeked.timestamp, Some(eq_archive::DETERMINISTIC_TIMESTAMP));
+    let peeked_eqg = peeked_eqg.unwrap();
+    assert_eq!(peeked_eqg.files, [("orc.mod".to_string(), 3)]);
+    assert_eq!(peeked_eqg.timestamp, None);
+    assert!(matches!(missing, Err(Error::IO(_))));
+}
@@ -0,0 +1,693 @@
+use std::convert::TryInto;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use eq_archive::testing::{self, EDGE_CASE_BLOCK_SIZES};
+use eq_archive::{
+    crc, raw, Archive, ArchiveBuilder, ArchiveWriter, Compression, Error, Inflate, LazyArchive,
+    ParseOptions, ProgressEvent, WriteOptions, DETERMINISTIC_TIMESTAMP, MAX_BLOCK_SIZE,
+};
+use proptest::prelude::*;
+
+fn options(block_size: usize) -> WriteOptions {
+    WriteOptions {
+        block_size,
+        ..Default::default()
+    }
+}
+
+fn assert_round_trips(files: &[(String, Vec<u8>)], options: WriteOptions) {
+    let buffer = testing::build(files, options);
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    assert!(archive.verify().is_empty());
+    for (name, data) in files {
+        let entry = archive.entry_by_crc(crc::filename_crc(name)).unwrap();
+        assert_eq!(entry.filename(), name);
+        assert_eq!(entry.size() as usize, data.len());
+        assert_eq!(entry.block_count(), entry.blocks().len());
+    }
+    let iterated: Vec<_> = archive.iter_files().map(Result::unwrap).collect();
+    assert_eq!(iterated, files);
+    let extracted: Vec<_> = archive.files().unwrap().collect();
+    assert_eq!(extracted, files);
+}
+
+#[test]
+fn matches_known_filename_crcs() {
+    // Only the terminator is hashed, which leaves the initial value
+    assert_eq!(crc::filename_crc(""), 0);
+    // CRC-32/CKSUM without its final XOR gives 0x89A1897F for "123456789",
+    // carried on over the terminator
+    assert_eq!(crc::filename_crc("123456789"), 0xEA4C_6FE1);
+    assert_eq!(crc::filename_crc("gfaydark.wld"), 0x5942_C027);
+    assert_eq!(crc::filename_crc("objects.wld"), 0x6131_59E6);
+
+    // Every packer keys the directory with this CRC
+    assert_eq!(raw::DIRECTORY_CRC, 0x6158_0AC9);
+    let buffer = testing::build(&[("orc.bmp".to_string(), b"orc".to_vec())], options(8192));
+    let archive = eq_archive::load(&buffer).unwrap();
+    assert_eq!(archive.directory_entry().unwrap().crc(), 0x6158_0AC9);
+}
+
+#[test]
+fn round_trips_edge_case_block_sizes() {
+    for &block_size in EDGE_CASE_BLOCK_SIZES.iter() {
+        let files = testing::edge_case_files(block_size);
+        let buffer = testing::build(&files, options(block_size));
+        let archive = eq_archive::load(&buffer).unwrap();
+
+        // Empty files share a pointer with the file after them so they can
+        // come back in a different order, but still with the right names
+        assert!(archive.verify().is_empty());
+        let mut extracted: Vec<_> = archive.files().unwrap().collect();
+        extracted.sort();
+        assert_eq!(extracted, files);
+    }
+}
+
+#[test]
+fn round_trips_empty_files() {
+    let files = vec![
+        ("empty.txt".to_string(), Vec::new()),
+        ("orc.bmp".to_string(), b"orc".to_vec()),
+        ("blank.wld".to_string(), Vec::new()),
+    ];
+    let strict = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let only_empty = testing::build(&files[..1], options(8192));
+    let archive = eq_archive::load_with(&only_empty, &strict).unwrap();
+    assert_eq!(archive.get("empty.txt").unwrap().unwrap(), b"");
+
+    let mut writer = ArchiveWriter::new(std::io::Cursor::new(Vec::new())).unwrap();
+    for (name, data) in &files {
+        writer.start_file(name).unwrap();
+        std::io::Write::write_all(&mut writer, data).unwrap();
+    }
+    let written = writer.finish().unwrap().into_inner();
+    let archive = eq_archive::load_with(&written, &strict).unwrap();
+    for (name, data) in &files {
+        assert_eq!(
+            archive
+                .entry_by_crc(crc::filename_crc(name))
+                .unwrap()
+                .block_count(),
+            data.len().min(1)
+        );
+        assert_eq!(&archive.get(name).unwrap().unwrap(), data);
+    }
+
+    // Other packers point empty files anywhere, even past the end of the data
+    let mut buffer = testing::build(&files, options(8192));
+    let (_, header) = raw::header(&buffer).unwrap();
+    let table = header.pointer() as usize + 4;
+    for record in (table..buffer.len() - 12).step_by(12) {
+        let crc = u32::from_le_bytes(buffer[record..record + 4].try_into().unwrap());
+        if crc == crc::filename_crc("blank.wld") {
+            buffer[record + 4..record + 8].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+    }
+    let lazy = LazyArchive::open(buffer.as_slice()).unwrap();
+    let archive = eq_archive::load_with(&buffer, &strict).unwrap();
+    for (name, data) in &files {
+        assert_eq!(&lazy.get(name).unwrap().unwrap(), data);
+        assert_eq!(&archive.get(name).unwrap().unwrap(), data);
+    }
+}
+
+#[test]
+fn round_trips_random_files() {
+    for seed in 0..8 {
+        assert_round_trips(&testing::random_files(seed, 16, 20_000), options(8192));
+    }
+}
+
+#[test]
+fn round_trips_without_footer() {
+    let files = testing::random_files(1, 4, 1000);
+    let buffer = testing::without_footer(testing::build(&files, options(8192)));
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    assert_eq!(archive.files().unwrap().collect::<Vec<_>>(), files);
+
+    // Archives already without one are left alone
+    let eqg = testing::build(&files, WriteOptions::eqg());
+    assert_eq!(testing::without_footer(eqg.clone()), eqg);
+    assert_eq!(testing::without_footer(buffer.clone()), buffer);
+}
+
+#[test]
+fn reads_ranges_across_blocks() {
+    let files = testing::random_files(4, 4, 200);
+    let buffer = testing::build(&files, options(7));
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    for (name, data) in &files {
+        for (offset, len) in [(0, 1), (3, 9), (6, 15), (data.len() - 1, 10)] {
+            let offset = offset.min(data.len());
+            let end = (offset + len).min(data.len());
+            let range = archive.read_range(name, offset as u64, len).unwrap();
+            assert_eq!(range.as_deref(), Some(&data[offset..end]));
+        }
+    }
+}
+
+#[test]
+fn streams_files_through_a_reader() {
+    let files = testing::random_files(23, 3, 20_000);
+    let archive = Archive::from_bytes(testing::build(&files, options(1000))).unwrap();
+
+    for (name, data) in &files {
+        let mut reader = archive.get_reader(name).unwrap();
+        // Reads smaller than a block, straddling the boundaries between them
+        let mut buf = [0; 300];
+        let mut streamed = Vec::new();
+        loop {
+            let read = reader.read(&mut buf).unwrap();
+            if read == 0 {
+                break;
+            }
+            streamed.extend_from_slice(&buf[..read]);
+        }
+        assert_eq!(&streamed, data);
+    }
+    assert!(archive.get_reader("missing.bmp").is_none());
+}
+
+#[test]
+fn gets_many_files_in_the_order_asked_for() {
+    let files = testing::random_files(26, 4, 5000);
+    let archive = Archive::from_bytes(testing::build(&files, options(1024))).unwrap();
+    let wanted = [
+        files[2].0.as_str(),
+        "missing.bmp",
+        files[0].0.as_str(),
+        files[2].0.as_str(),
+    ];
+
+    let got = archive.get_many(&wanted).unwrap();
+    assert_eq!(
+        got,
+        [
+            Some(files[2].1.clone()),
+            None,
+            Some(files[0].1.clone()),
+            Some(files[2].1.clone())
+        ]
+    );
+    assert!(archive.get_many(&[]).unwrap().is_empty());
+    #[cfg(feature = "parallel")]
+    assert_eq!(archive.get_many_par(&wanted).unwrap(), got);
+}
+
+#[test]
+fn copies_compressed_blocks_without_inflating() {
+    let files = vec![
+        ("orc.bmp".to_string(), b"orc ".repeat(1000)),
+        ("empty.txt".to_string(), Vec::new()),
+    ];
+    let buffer = testing::build(&files, options(1024));
+    let archive = Archive::from_bytes(buffer.clone()).unwrap();
+    let entry = archive.entry_by_crc(crc::filename_crc("orc.bmp")).unwrap();
+
+    // Each block's zlib stream back to back, their sizes given by the blocks
+    let raw = archive.get_raw("orc.bmp").unwrap();
+    assert_eq!(raw, entry.compressed_bytes(&buffer));
+    assert_eq!(
+        raw.len(),
+        entry
+            .blocks()
+            .iter()
+            .map(|block| block.compressed_size as usize)
+            .sum::<usize>()
+    );
+    let mut inflated = Vec::new();
+    let mut streams = &raw[..];
+    for block in entry.blocks() {
+        let (stream, rest) = streams.split_at(block.compressed_size as usize);
+        eq_archive::Zlib
+            .inflate(stream, block.uncompressed_size as usize, &mut inflated)
+            .unwrap();
+        streams = rest;
+    }
+    assert_eq!(inflated, files[0].1);
+
+    assert_eq!(archive.get_raw("empty.txt").unwrap(), b"");
+    assert!(archive.get_raw("missing.bmp").is_none());
+}
+
+#[test]
+fn deduplicates_identical_files() {
+    let texture = testing::random_files(3, 1, 5000).remove(0).1;
+    let files = vec![
+        ("orc.bmp".to_string(), texture.clone()),
+        ("orc2.bmp".to_string(), texture),
+    ];
+    let deduplicated = testing::build(&files, options(8192));
+    let duplicated = testing::build(
+        &files,
+        WriteOptions {
+            deduplicate: false,
+            ..options(8192)
+        },
+    );
+    assert!(deduplicated.len() < duplicated.len());
+
+    let strict = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    let archive = eq_archive::load_with(&deduplicated, &strict).unwrap();
+    for (name, data) in &files {
+        assert_eq!(archive.get(name).unwrap().as_ref(), Some(data));
+    }
+}
+
+#[test]
+fn seeks_within_files() {
+    use std::io::{Seek, SeekFrom};
+
+    let files = testing::random_files(16, 1, 20_000);
+    let data = &files[0].1;
+    let archive = Archive::from_bytes(testing::build(&files, options(4096))).unwrap();
+    let mut file = archive.open(&files[0].0).unwrap();
+    assert_eq!(file.len(), data.len() as u64);
+
+    // Across a block boundary
+    let mut buf = vec![0; 200];
+    assert_eq!(file.seek(SeekFrom::Start(4000)).unwrap(), 4000);
+    file.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, data[4000..4200]);
+
+    let end = data.len() - 10;
+    assert_eq!(file.seek(SeekFrom::End(-10)).unwrap(), end as u64);
+    let mut rest = Vec::new();
+    file.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, data[end..]);
+
+    file.seek(SeekFrom::Current(-(data.len() as i64))).unwrap();
+    file.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, data[..200]);
+
+    assert!(file.seek(SeekFrom::Current(-201)).is_err());
+    file.seek(SeekFrom::End(100)).unwrap();
+    assert_eq!(file.read(&mut buf).unwrap(), 0);
+    assert!(archive.open("missing.bmp").is_none());
+}
+
+#[test]
+fn reads_uncompressed_blocks() {
+    // The second file starts like a zlib stream and has to be wrapped
+    let files = vec![
+        ("orc.txt".to_string(), b"raw text".to_vec()),
+        ("orc.bin".to_string(), vec![0x78, 0x9c, 0x01, 0x02]),
+    ];
+    let options = WriteOptions {
+        compression: Compression::Uncompressed,
+        ..options(8192)
+    };
+    assert_round_trips(&files, options);
+
+    let buffer = testing::build(&files, options);
+    let archive = eq_archive::load(&buffer).unwrap();
+    let stored = |name| {
+        let entry = archive.entry_by_crc(crc::filename_crc(name)).unwrap();
+        entry.blocks()[0].is_stored(&buffer)
+    };
+    assert!(stored("orc.txt"));
+    assert!(!stored("orc.bin"));
+}
+
+#[test]
+fn threads_write_the_same_blocks() {
+    let files = testing::random_files(6, 4, 40000);
+    let threaded = WriteOptions {
+        threads: 4,
+        ..options(1024)
+    };
+    let single = testing::without_footer(testing::build(&files, options(1024)));
+    let multi = testing::without_footer(testing::build(&files, threaded));
+    assert_eq!(single, multi);
+}
+
+#[test]
+fn round_trips_client_block_size() {
+    assert_eq!(WriteOptions::default().block_size, 8192);
+
+    let files = testing::random_files(9, 6, 40_000);
+    let buffer = testing::build(&files, WriteOptions::default());
+    let archive = eq_archive::load(&buffer).unwrap();
+    for entry in archive.entries.iter().filter(|entry| entry.size() > 0) {
+        let (last, full) = entry.blocks().split_last().unwrap();
+        assert!(full.iter().all(|block| block.uncompressed_size == 8192));
+        assert!(last.uncompressed_size <= 8192);
+    }
+    assert_round_trips(&files, WriteOptions::default());
+}
+
+#[test]
+fn rejects_invalid_block_sizes() {
+    for &block_size in [0, MAX_BLOCK_SIZE + 1].iter() {
+        let mut builder = ArchiveBuilder::with_options(options(block_size));
+        builder.add_file("orc.bmp", vec![1; 100]);
+        let error = builder.write(Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    let files = testing::random_files(10, 2, 1000);
+    assert_round_trips(&files, options(MAX_BLOCK_SIZE));
+}
+
+#[test]
+fn data_spans_cover_each_entry() {
+    let mut files = testing::random_files(11, 5, 30_000);
+    files.push(("empty.txt".to_string(), Vec::new()));
+    let buffer = testing::build(&files, options(4096));
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    let mut spans: Vec<_> = archive
+        .entries
+        .iter()
+        .map(|entry| entry.data_span())
+        .collect();
+    spans.sort_by_key(|span| (span.start, span.end));
+    assert_eq!(spans[0].start, 12);
+    for pair in spans.windows(2) {
+        assert!(pair[0].end <= pair[1].start);
+    }
+    for entry in &archive.entries {
+        let span = entry.data_span();
+        let headers = 8 * entry.block_count() as u64;
+        assert_eq!(
+            span.end - span.start,
+            u64::from(entry.compressed_size()) + headers
+        );
+        assert_eq!(span.start, u64::from(entry.pointer()));
+    }
+}
+
+#[test]
+fn builds_the_same_bytes_in_memory() {
+    let mut builder = ArchiveBuilder::new();
+    for (name, data) in testing::random_files(13, 3, 5000) {
+        builder.add_file(&name, data);
+    }
+    let bytes = builder.in_memory().unwrap();
+    assert_eq!(builder.in_memory().unwrap(), bytes);
+
+    let archive = Archive::from_bytes(bytes).unwrap();
+    let timestamp = UNIX_EPOCH + Duration::from_secs(DETERMINISTIC_TIMESTAMP.into());
+    assert_eq!(archive.timestamp(), Some(timestamp));
+    assert_eq!(archive.filenames().len(), 3);
+
+    // A timestamp that was set is kept
+    builder.set_timestamp(UNIX_EPOCH);
+    let archive = Archive::from_bytes(builder.in_memory().unwrap()).unwrap();
+    assert_eq!(archive.timestamp(), Some(UNIX_EPOCH));
+}
+
+#[test]
+fn checksums_inflated_contents() {
+    // Inflates the raw deflate stream and never looks at the zlib trailer
+    #[derive(Debug)]
+    struct Lenient;
+    impl Inflate for Lenient {
+        fn inflate(&self, block: &[u8], _: usize, out: &mut Vec<u8>) -> std::io::Result<()> {
+            flate2::read::DeflateDecoder::new(&block[2..])
+                .read_to_end(out)
+                .map(|_| ())
+        }
+    }
+
+    let files = testing::random_files(14, 4, 20_000);
+    let mut buffer = testing::build(&files, options(4096));
+    let archive = eq_archive::load(&buffer).unwrap();
+    assert!(archive
+        .entries_info()
+        .iter()
+        .all(|info| info.content_checksum.is_none()));
+    for (info, (_, data)) in archive
+        .entries_info_with_checksums(true)
+        .unwrap()
+        .iter()
+        .zip(&files)
+    {
+        assert_eq!(info.content_checksum, Some(crc32fast::hash(data)));
+    }
+
+    // Break the checksum of the first deflated block
+    let block = archive
+        .entries
+        .iter()
+        .flat_map(|entry| entry.blocks())
+        .find(|block| !block.is_stored(&buffer))
+        .unwrap();
+    let (offset, trailer) = (
+        block.offset(),
+        block.offset() + block.compressed_size as usize - 1,
+    );
+    buffer[trailer] ^= 0xff;
+    let options = ParseOptions {
+        inflater: Some(Arc::new(Lenient)),
+        ..Default::default()
+    };
+    let archive = eq_archive::load_with(&buffer, &options).unwrap();
+    assert!(archive.entries_info_with_checksums(false).is_ok());
+    match archive.entries_info_with_checksums(true) {
+        Err(Error::ChecksumMismatch { offset: at, .. }) => assert_eq!(at, offset),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn reports_progress() {
+    static EVENTS: Mutex<Vec<ProgressEvent>> = Mutex::new(Vec::new());
+    fn record(event: ProgressEvent) {
+        EVENTS.lock().unwrap().push(event);
+    }
+    let take = || std::mem::take(&mut *EVENTS.lock().unwrap());
+    let event = |entries_processed, entry_count, uncompressed_bytes| ProgressEvent {
+        entries_processed,
+        entry_count,
+        uncompressed_bytes,
+    };
+
+    let files = vec![
+        ("orc.bmp".to_string(), vec![1; 3000]),
+        ("elf.bmp".to_string(), vec![2; 500]),
+    ];
+    let written = WriteOptions {
+        progress: Some(record),
+        ordering: eq_archive::FileOrder::Insertion,
+        ..options(1024)
+    };
+    let buffer = testing::build(&files, written);
+    assert_eq!(take(), [event(1, Some(2), 3000), event(2, Some(2), 3500)]);
+
+    // Parsing counts the directory's entry too, but inflates nothing
+    let parsed = ParseOptions {
+        progress: Some(record),
+        ..Default::default()
+    };
+    let archive = eq_archive::load_with(&buffer, &parsed).unwrap();
+    assert_eq!(
+        take(),
+        [
+            event(1, Some(3), 0),
+            event(2, Some(3), 0),
+            event(3, Some(3), 0)
+        ]
+    );
+    archive.files().unwrap().for_each(drop);
+    assert_eq!(take(), [event(1, Some(2), 3000), event(2, Some(2), 3500)]);
+
+    // Files streamed into a writer aren't counted up front
+    let mut writer =
+        ArchiveWriter::with_options(std::io::Cursor::new(Vec::new()), written).unwrap();
+    for (name, data) in &files {
+        writer.start_file(name).unwrap();
+        std::io::Write::write_all(&mut writer, data).unwrap();
+    }
+    writer.finish().unwrap();
+    assert_eq!(take(), [event(1, None, 3000), event(2, None, 3500)]);
+}
+
+#[test]
+fn streams_after_existing_data() {
+    let mut cursor = std::io::Cursor::new(b"PREFIX".to_vec());
+    cursor.set_position(6);
+    let mut writer = ArchiveWriter::new(cursor).unwrap();
+    writer.start_file("orc.bmp").unwrap();
+    std::io::Write::write_all(&mut writer, &b"orc ".repeat(100)).unwrap();
+    let written = writer.finish().unwrap().into_inner();
+
+    assert_eq!(&written[..6], b"PREFIX");
+    let archive = eq_archive::load(&written[6..]).unwrap();
+    assert_eq!(
+        archive.get("orc.bmp").unwrap().unwrap(),
+        b"orc ".repeat(100)
+    );
+}
+
+#[test]
+fn refuses_to_write_past_4_gib() {
+    // Discards everything, only the writer's own offsets matter
+    struct Sink(u64);
+    impl std::io::Write for Sink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len() as u64;
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl std::io::Seek for Sink {
+        fn seek(&mut self, _: std::io::SeekFrom) -> std::io::Result<u64> {
+            Ok(self.0)
+        }
+    }
+
+    let options = WriteOptions {
+        compression: Compression::Uncompressed,
+        block_size: MAX_BLOCK_SIZE,
+        ..Default::default()
+    };
+    let mut writer = ArchiveWriter::with_options(Sink(0), options).unwrap();
+    writer.start_file("huge.wld").unwrap();
+    let chunk = vec![1; MAX_BLOCK_SIZE];
+    let error = loop {
+        if let Err(e) = std::io::Write::write_all(&mut writer, &chunk) {
+            break e;
+        }
+    };
+    assert_eq!(error.kind(), std::io::ErrorKind::FileTooLarge);
+}
+
+#[test]
+fn writes_retail_layout() {
+    let texture = testing::random_files(8, 1, 30000).remove(0).1;
+    let files = vec![
+        ("orc.bmp".to_string(), texture.clone()),
+        ("orc2.bmp".to_string(), texture),
+    ];
+    let buffer = testing::build(&files, WriteOptions::retail());
+    let archive = eq_archive::load(&buffer).unwrap();
+
+    let (_, header) = raw::header(&buffer).unwrap();
+    let (mut table, count) = {
+        let table = &buffer[header.pointer() as usize..];
+        (
+            &table[4..],
+            u32::from_le_bytes(table[..4].try_into().unwrap()),
+        )
+    };
+    let mut crcs = Vec::new();
+    let mut pointers = Vec::new();
+    for _ in 0..count {
+        let (rest, entry) = raw::entry(table).unwrap();
+        crcs.push(entry.crc());
+        pointers.push((entry.pointer(), entry.crc()));
+        table = rest;
+    }
+    let (rest, footer) = raw::footer(table).unwrap();
+    assert!(rest.is_empty());
+    assert!(footer.timestamp() > 0);
+
+    // The entry table is sorted by CRC and the directory comes after every file
+    assert!(crcs.windows(2).all(|pair| pair[0] < pair[1]));
+    pointers.sort_unstable();
+    assert_eq!(pointers.last().unwrap().1, raw::DIRECTORY_CRC);
+    pointers.dedup_by_key(|&mut (pointer, _)| pointer);
+    assert_eq!(pointers.len(), 3);
+
+    for entry in &archive.entries {
+        let (last, full) = entry.blocks().split_last().unwrap();
+        assert!(full.iter().all(|block| block.uncompressed_size == 8192));
+        assert!(last.uncompressed_size <= 8192);
+    }
+}
+
+#[test]
+fn reproduces_original_bytes() {
+    let files = testing::random_files(2, 4, 1000);
+    let buffer = testing::without_footer(testing::build(&files, options(8192)));
+    let archive = eq_archive::load(&buffer).unwrap().into_owned();
+
+    assert_eq!(archive.as_bytes(), buffer.as_slice());
+}
+
+#[test]
+fn stops_inflating_past_the_stored_size() {
+    // Records the most any block inflated to past its stored size
+    #[derive(Debug, Default)]
+    struct Measured(std::sync::atomic::AtomicUsize);
+    impl Inflate for Measured {
+        fn inflate(&self, block: &[u8], size: usize, out: &mut Vec<u8>) -> std::io::Result<()> {
+            let start = out.len();
+            let result = eq_archive::Zlib.inflate(block, size, out);
+            let overshoot = (out.len() - start).saturating_sub(size);
+            self.0
+                .fetch_max(overshoot, std::sync::atomic::Ordering::Relaxed);
+            result
+        }
+    }
+
+    // A megabyte of zeros deflates to about a kilobyte, then claims to be 10 bytes
+    let files = vec![("bomb.bin".to_string(), vec![0; 1 << 20])];
+    let mut buffer = testing::build(&files, options(1 << 20));
+    let (_, header) = raw::header(&buffer).unwrap();
+    let table = header.pointer() as usize + 4;
+    let entry = (0..2)
+        .map(|n| table + n * 12)
+        .find(|&entry| buffer[entry..entry + 4] == crc::filename_crc("bomb.bin").to_le_bytes())
+        .unwrap();
+    let pointer = u32::from_le_bytes(buffer[entry + 4..entry + 8].try_into().unwrap()) as usize;
+    buffer[pointer + 4..pointer + 8].copy_from_slice(&10u32.to_le_bytes());
+    buffer[entry + 8..entry + 12].copy_from_slice(&10u32.to_le_bytes());
+
+    let inflater = Arc::new(Measured::default());
+    let options = ParseOptions {
+        inflater: Some(inflater.clone()),
+        ..Default::default()
+    };
+    let archive = eq_archive::load_with(&buffer, &options).unwrap();
+    assert!(matches!(
+        archive.get("bomb.bin"),
+        Err(Error::SizeMismatch {
+            expected: 10,
+            actual: 11,
+            ..
+        })
+    ));
+    assert_eq!(inflater.0.load(std::sync::atomic::Ordering::Relaxed), 1);
+}
+
+fn compression() -> impl Strategy<Value = Compression> {
+    prop_oneof![
+        Just(Compression::Fast),
+        Just(Compression::Best),
+        Just(Compression::Stored),
+        Just(Compression::Uncompressed),
+        (0u32..10).prop_map(Compression::Level),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn round_trips_any_files(
+        files in prop::collection::btree_map("[a-z0-9_]{1,12}\\.(bmp|wld|dds)", prop::collection::vec(any::<u8>(), 1..20_000), 0..8),
+        // Single byte blocks are covered above, they're too slow to run here
+        block_size in prop::sample::select(EDGE_CASE_BLOCK_SIZES[1..].to_vec()),
+        compression in compression(),
+    ) {
+        let files: Vec<_> = files.into_iter().collect();
+        let options = WriteOptions { compression, block_size, ..Default::default() };
+        assert_round_trips(&files, options);
+    }
+}
@@ -0,0 +1,265 @@
+use eq_archive::testing;
+use eq_archive::{crc, Archive, ArchiveBuilder, FileKind, FileOrder, WriteOptions};
+
+fn build(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut builder = ArchiveBuilder::new();
+    for (name, data) in files {
+        builder.add_file(name, data.to_vec());
+    }
+    let mut buffer = Vec::new();
+    builder.write(&mut buffer).unwrap();
+    buffer
+}
+
+fn options(block_size: usize) -> WriteOptions {
+    WriteOptions {
+        block_size,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn classifies_files() {
+    let files = vec![
+        ("gfaydark.wld".to_string(), b"\x02\x3DPT\x00\x00".to_vec()),
+        ("grass.bmp".to_string(), b"BM\x00\x00".to_vec()),
+        ("grass.dds".to_string(), b"DDS \x00".to_vec()),
+        (
+            "orc.wav".to_string(),
+            b"RIFF\x00\x00\x00\x00WAVEfmt ".to_vec(),
+        ),
+        ("gfaydark.txt".to_string(), b"1, 2, 3\r\n".to_vec()),
+        ("orc.mod".to_string(), vec![0, 1, 2, 3]),
+    ];
+    let archive = Archive::from_bytes(testing::build(&files, options(8192))).unwrap();
+
+    let kinds: Vec<_> = archive
+        .classified_files()
+        .map(|file| file.unwrap().1)
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            FileKind::Wld,
+            FileKind::Bmp,
+            FileKind::Dds,
+            FileKind::Wav,
+            FileKind::Txt,
+            FileKind::Unknown
+        ]
+    );
+}
+
+#[test]
+fn finds_files_by_extension_and_the_zone_wld() {
+    let buffer = build(&[
+        ("objects.wld", b"objects"),
+        ("GFAYDARK.WLD", b"zone"),
+        ("lights.wld", b"lights"),
+        ("grass.bmp", b"grass"),
+        ("wld.bmp", b"not a wld"),
+    ]);
+    let archive = Archive::from_bytes(buffer).unwrap();
+    assert_eq!(
+        archive.files_with_extension("wld"),
+        ["objects.wld", "GFAYDARK.WLD", "lights.wld"]
+    );
+    assert_eq!(
+        archive.files_with_extension("BMP"),
+        ["grass.bmp", "wld.bmp"]
+    );
+    assert!(archive.files_with_extension("dds").is_empty());
+    // The zone's own .wld is preferred over its objects and lights
+    assert_eq!(archive.wld().unwrap().unwrap(), b"zone");
+
+    let objects = Archive::from_bytes(build(&[
+        ("lights.wld", b"lights"),
+        ("objects.wld", b"objects"),
+    ]))
+    .unwrap();
+    assert_eq!(objects.wld().unwrap().unwrap(), b"lights");
+    let textures = Archive::from_bytes(build(&[("grass.bmp", b"grass")])).unwrap();
+    assert!(textures.wld().unwrap().is_none());
+}
+
+#[test]
+fn selects_files_with_wildcards() {
+    let buffer = build(&[
+        ("ORC.bmp", b""),
+        ("orc_chr.mod", b""),
+        ("orc2.bmp", b""),
+        ("elf.bmp", b""),
+    ]);
+    let archive = Archive::from_bytes(buffer).unwrap();
+    let glob = |pattern| {
+        let mut matched = archive.glob(pattern);
+        matched.sort();
+        matched
+    };
+    assert_eq!(glob("orc*"), ["ORC.bmp", "orc2.bmp", "orc_chr.mod"]);
+    assert_eq!(glob("*.BMP"), ["ORC.bmp", "elf.bmp", "orc2.bmp"]);
+    assert_eq!(glob("orc?.bmp"), ["orc2.bmp"]);
+    assert_eq!(archive.glob("*"), archive.filenames());
+    assert!(glob("orc").is_empty());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn selects_files_with_a_regex() {
+    let buffer = build(&[("ORC.bmp", b""), ("orc2.bmp", b""), ("elf.bmp", b"")]);
+    let archive = Archive::from_bytes(buffer).unwrap();
+    let regex = regex::Regex::new(r"^orc\d*\.bmp$").unwrap();
+    // Unlike globs, regexes are case sensitive unless asked otherwise
+    assert_eq!(archive.matching(&regex), ["orc2.bmp"]);
+    let regex = regex::Regex::new(r"(?i)^orc\d*\.bmp$").unwrap();
+    let mut matched = archive.matching(&regex);
+    matched.sort();
+    assert_eq!(matched, ["ORC.bmp", "orc2.bmp"]);
+}
+
+#[test]
+fn orders_files() {
+    let files: Vec<_> = ["orc.bmp", "Bear.bmp", "gnoll.bmp"]
+        .iter()
+        .map(|name| (name.to_string(), name.as_bytes().to_vec()))
+        .collect();
+    let written = |ordering| {
+        let options = WriteOptions {
+            ordering,
+            ..options(8192)
+        };
+        let archive = Archive::from_bytes(testing::build(&files, options)).unwrap();
+        archive.filenames().to_vec()
+    };
+
+    assert_eq!(
+        written(FileOrder::Insertion),
+        ["orc.bmp", "Bear.bmp", "gnoll.bmp"]
+    );
+    assert_eq!(
+        written(FileOrder::Name),
+        ["Bear.bmp", "gnoll.bmp", "orc.bmp"]
+    );
+    let by_crc = written(FileOrder::Crc);
+    assert!(by_crc
+        .windows(2)
+        .all(|pair| crc::filename_crc(&pair[0]) < crc::filename_crc(&pair[1])));
+}
+
+#[test]
+fn sums_sizes_and_blocks() {
+    let files = vec![
+        ("orc.bmp".to_string(), b"orc ".repeat(1000)),
+        ("zone.wld".to_string(), b"zone".repeat(2000)),
+        ("readme".to_string(), Vec::new()),
+    ];
+    let archive = Archive::from_bytes(testing::build(&files, options(1024))).unwrap();
+    let stats = archive.stats();
+
+    assert_eq!(stats.entry_count, 3);
+    // 4000 and 8000 bytes in blocks of 1024, the empty file has none
+    assert_eq!(stats.block_count, 4 + 8);
+    assert_eq!(stats.uncompressed_size, 12_000);
+    let compressed: u64 = archive
+        .entries_info()
+        .iter()
+        .map(|info| u64::from(info.compressed_size))
+        .sum();
+    assert_eq!(stats.compressed_size, compressed);
+    assert!(stats.compression_ratio < 0.5);
+    assert_eq!(
+        stats.compression_ratio,
+        compressed as f64 / stats.uncompressed_size as f64
+    );
+    assert_eq!(stats.largest_entry, Some(("zone.wld".to_string(), 8000)));
+
+    let empty = Archive::from_bytes(build(&[])).unwrap().stats();
+    assert_eq!((empty.entry_count, empty.block_count), (0, 0));
+    assert_eq!(empty.compression_ratio, 1.0);
+    assert_eq!(empty.largest_entry, None);
+}
+
+#[test]
+fn analyzes_sizes_and_duplicates() {
+    let files = vec![
+        ("orc.bmp".to_string(), vec![1; 5000]),
+        ("elf.BMP".to_string(), vec![2; 300]),
+        ("orc2.bmp".to_string(), vec![1; 5000]),
+        ("zone.wld".to_string(), vec![3; 9000]),
+        ("readme".to_string(), Vec::new()),
+        ("notes".to_string(), Vec::new()),
+    ];
+    let archive = Archive::from_bytes(testing::build(&files, options(8192))).unwrap();
+    let analysis = archive.analyze(2).unwrap();
+
+    let bmp = &analysis.by_extension["bmp"];
+    assert_eq!((bmp.count, bmp.uncompressed_size), (3, 10_300));
+    assert_eq!(analysis.by_extension["wld"].count, 1);
+    assert_eq!(analysis.by_extension[""].count, 2);
+    assert_eq!(
+        analysis.largest,
+        [
+            ("zone.wld".to_string(), 9000),
+            ("orc.bmp".to_string(), 5000)
+        ]
+    );
+    assert_eq!(analysis.duplicates.len(), 1);
+    assert_eq!(analysis.duplicates[0].filenames, ["orc.bmp", "orc2.bmp"]);
+    assert_eq!(analysis.duplicate_size(), 5000);
+}
+
+#[test]
+fn searches_a_directory() {
+    let dir = std::env::temp_dir().join(format!("eq_archive_search_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("gfaydark.s3d"), build(&[("orc.bmp", b"orc")])).unwrap();
+    std::fs::write(
+        dir.join("orc.eqg"),
+        testing::without_footer(build(&[("ORC_CHR.mod", b"orc")])),
+    )
+    .unwrap();
+    std::fs::write(dir.join("qeynos.s3d"), build(&[("guard.bmp", b"guard")])).unwrap();
+    std::fs::write(dir.join("notes.s3d"), b"not an archive").unwrap();
+
+    let found = eq_archive::search(&dir, "orc*").unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        found,
+        [
+            (dir.join("gfaydark.s3d"), "orc.bmp".to_string()),
+            (dir.join("orc.eqg"), "ORC_CHR.mod".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn finds_related_archives() {
+    let dir = std::env::temp_dir().join(format!("eq_archive_related_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in [
+        "gfaydark.s3d",
+        "gfaydark_2_obj.s3d",
+        "gfaydark.eqg",
+        "gfaydark_chr.s3d",
+        "qeynos.s3d",
+    ] {
+        std::fs::write(dir.join(name), build(&[(name, b"")])).unwrap();
+    }
+
+    let set = eq_archive::related(dir.join("gfaydark_2_obj.s3d")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let mounted: Vec<_> = set
+        .archives()
+        .iter()
+        .map(|archive| archive.filenames()[0].as_str())
+        .collect();
+    assert_eq!(
+        mounted,
+        [
+            "gfaydark.s3d",
+            "gfaydark.eqg",
+            "gfaydark_2_obj.s3d",
+            "gfaydark_chr.s3d"
+        ]
+    );
+}
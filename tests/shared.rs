@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use eq_archive::testing;
+use eq_archive::{Archive, SharedArchive, WriteOptions};
+
+fn options(block_size: usize) -> WriteOptions {
+    WriteOptions {
+        block_size,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn caches_recently_used_files() {
+    let files: Vec<_> = ["a.bmp", "b.bmp", "c.bmp", "big.bmp"]
+        .iter()
+        .zip([1000, 1000, 1000, 3000])
+        .map(|(name, size)| (name.to_string(), vec![name.as_bytes()[0]; size]))
+        .collect();
+    let archive = Archive::from_bytes(testing::build(&files, options(8192))).unwrap();
+    let shared = Arc::new(SharedArchive::with_capacity(archive, 2500));
+
+    let a = shared.get("a.bmp").unwrap().unwrap();
+    shared.get("b.bmp").unwrap().unwrap();
+    assert!(Arc::ptr_eq(&shared.get("a.bmp").unwrap().unwrap(), &a));
+    // b was used longest ago so it makes room for c
+    shared.get("c.bmp").unwrap().unwrap();
+    assert!(shared.is_cached("a.bmp"));
+    assert!(!shared.is_cached("b.bmp"));
+    assert!(shared.is_cached("c.bmp"));
+    assert_eq!(*shared.get("big.bmp").unwrap().unwrap(), files[3].1);
+    assert!(!shared.is_cached("big.bmp"));
+    assert!(shared.get("missing.bmp").unwrap().is_none());
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let shared = shared.clone();
+            std::thread::spawn(move || shared.get("a.bmp").unwrap().unwrap())
+        })
+        .collect();
+    for thread in threads {
+        assert!(Arc::ptr_eq(&thread.join().unwrap(), &a));
+    }
+
+    shared.clear_cache();
+    assert!(!shared.is_cached("a.bmp"));
+    assert_eq!(*shared.get("a.bmp").unwrap().unwrap(), files[0].1);
+}
+
+#[test]
+fn prefetches_files_in_the_background() {
+    let files = testing::random_files(5, 4, 20_000);
+    let archive = Archive::from_bytes(testing::build(&files, options(8192))).unwrap();
+    let shared = Arc::new(SharedArchive::new(archive));
+
+    let names = [files[0].0.as_str(), files[2].0.as_str(), "missing.bmp"];
+    shared.prefetch_files(&names).join().unwrap().unwrap();
+
+    assert!(shared.is_cached(&files[0].0));
+    assert!(!shared.is_cached(&files[1].0));
+    assert!(shared.is_cached(&files[2].0));
+    assert_eq!(*shared.get(&files[2].0).unwrap().unwrap(), files[2].1);
+}
+
+#[test]
+fn inflates_once_for_concurrent_misses() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    // Slow enough that every thread misses the cache while the first inflates
+    #[derive(Debug, Default)]
+    struct Counted(AtomicUsize);
+    impl eq_archive::Inflate for Counted {
+        fn inflate(&self, block: &[u8], size: usize, out: &mut Vec<u8>) -> std::io::Result<()> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            std::thread::sleep(Duration::from_millis(50));
+            eq_archive::Zlib.inflate(block, size, out)
+        }
+    }
+
+    let files = vec![("orc.bmp".to_string(), b"orc ".repeat(100))];
+    let buffer = testing::build(&files, options(8192));
+    let inflater = Arc::new(Counted::default());
+    let parse = eq_archive::ParseOptions {
+        inflater: Some(inflater.clone()),
+        ..Default::default()
+    };
+    let archive = eq_archive::load_with(&buffer, &parse).unwrap();
+    let shared = SharedArchive::new(archive);
+    // Reading the directory counts too
+    inflater.0.store(0, Ordering::Relaxed);
+
+    let results: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| scope.spawn(|| shared.get("orc.bmp").unwrap().unwrap()))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    assert_eq!(inflater.0.load(Ordering::Relaxed), 1);
+    for data in &results {
+        assert!(Arc::ptr_eq(data, &results[0]));
+        assert_eq!(**data, files[0].1);
+    }
+}
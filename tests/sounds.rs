@@ -0,0 +1,80 @@
+#![cfg(feature = "sounds")]
+
+use std::time::Duration;
+
+use eq_archive::{testing, Archive, PcmFormat, Sound, SoundFormat, SoundInfo, WriteOptions};
+
+const PCM: PcmFormat = PcmFormat {
+    sample_rate: 22050,
+    channels: 1,
+    bits_per_sample: 16,
+};
+
+fn sounds(files: Vec<(String, Vec<u8>)>) -> Vec<Sound> {
+    let archive = Archive::from_bytes(testing::build(&files, WriteOptions::default())).unwrap();
+    let mut sounds: Vec<_> = archive.sounds().map(Result::unwrap).collect();
+    sounds.sort_by(|a, b| a.filename.cmp(&b.filename));
+    sounds
+}
+
+#[test]
+fn finds_sounds_and_wraps_raw_samples() {
+    // A second of samples
+    let samples = vec![0; 44100];
+    let mut xmi = b"FORM\0\0\0\x0eXDIR".to_vec();
+    xmi.extend_from_slice(&[0; 16]);
+    let files = vec![
+        ("hit.wav".to_string(), samples.clone()),
+        ("music.xmi".to_string(), xmi),
+        ("theme.mid".to_string(), b"MThd\0\0\0\x06".to_vec()),
+        ("orc.bmp".to_string(), b"BM".repeat(10)),
+    ];
+    let found = sounds(files);
+    let formats: Vec<_> = found
+        .iter()
+        .map(|sound| (sound.filename.as_str(), sound.info.format))
+        .collect();
+    assert_eq!(
+        formats,
+        [
+            ("hit.wav", SoundFormat::Raw),
+            ("music.xmi", SoundFormat::Xmi),
+            ("theme.mid", SoundFormat::Midi)
+        ]
+    );
+    assert_eq!(found[0].info.pcm, None);
+    assert_eq!(found[1].to_wav(PCM).unwrap(), None);
+
+    let wav = found[0].to_wav(PCM).unwrap().unwrap();
+    assert_eq!(wav.len(), 44 + samples.len());
+    assert_eq!(&wav[44..], &samples[..]);
+    let info = SoundInfo::detect("hit.wav", &wav).unwrap();
+    assert_eq!(info.format, SoundFormat::Wav);
+    assert_eq!(info.pcm, Some(PCM));
+    assert_eq!(info.duration, Some(Duration::from_secs(1)));
+
+    // WAVs already have a header and come back as they are
+    let wrapped = sounds(vec![("hit.wav".to_string(), wav.clone())]).remove(0);
+    assert_eq!(wrapped.info, info);
+    assert_eq!(wrapped.to_wav(PCM).unwrap(), Some(wav));
+}
+
+#[test]
+fn refuses_wav_headers_that_overflow() {
+    let raw = sounds(vec![("hit.wav".to_string(), vec![0; 100])]).remove(0);
+    let channels = PcmFormat {
+        channels: u16::MAX,
+        ..PCM
+    };
+    let sample_rate = PcmFormat {
+        sample_rate: u32::MAX,
+        ..PCM
+    };
+    for pcm in [channels, sample_rate] {
+        assert!(
+            matches!(raw.to_wav(pcm), Err(eq_archive::Error::IO(_))),
+            "{:?}",
+            pcm
+        );
+    }
+}
@@ -0,0 +1,56 @@
+#![cfg(feature = "textures")]
+
+use eq_archive::{testing, Archive, TextureFormat, TextureInfo, WriteOptions};
+
+fn header(magic: &[u8], fields: &[(usize, &[u8])]) -> Vec<u8> {
+    let mut data = vec![0; 64];
+    data[..magic.len()].copy_from_slice(magic);
+    for (offset, bytes) in fields {
+        data[*offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+    data
+}
+
+#[test]
+fn finds_textures_by_their_contents() {
+    // A top down bitmap has a negative height
+    let bmp = header(
+        b"BM",
+        &[(18, &64i32.to_le_bytes()), (22, &(-32i32).to_le_bytes())],
+    );
+    let dds = header(
+        b"DDS ",
+        &[(12, &16u32.to_le_bytes()), (16, &8u32.to_le_bytes())],
+    );
+    let tga = header(b"", &[(12, &4u16.to_le_bytes()), (14, &2u16.to_le_bytes())]);
+    let files = vec![
+        ("grass.bmp".to_string(), bmp),
+        ("named.bin".to_string(), dds),
+        ("sky.tga".to_string(), tga.clone()),
+        ("not_a_texture.bmp".to_string(), b"orc".repeat(100)),
+    ];
+    let archive = Archive::from_bytes(testing::build(&files, WriteOptions::default())).unwrap();
+
+    let mut textures: Vec<_> = archive
+        .textures()
+        .map(|texture| {
+            let texture = texture.unwrap();
+            (texture.filename, texture.info)
+        })
+        .collect();
+    textures.sort_by(|a, b| a.0.cmp(&b.0));
+    let info = |format, width, height| TextureInfo {
+        format,
+        width,
+        height,
+    };
+    assert_eq!(
+        textures,
+        [
+            ("grass.bmp".to_string(), info(TextureFormat::Bmp, 64, 32)),
+            ("named.bin".to_string(), info(TextureFormat::Dds, 8, 16)),
+            ("sky.tga".to_string(), info(TextureFormat::Tga, 4, 2)),
+        ]
+    );
+    assert_eq!(TextureInfo::detect("sky.bin", &tga), None);
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "zip")]
+
+use std::io::Cursor;
+use std::time::{Duration, UNIX_EPOCH};
+
+use eq_archive::{testing, Archive, WriteOptions, DETERMINISTIC_TIMESTAMP};
+
+#[test]
+fn converts_to_and_from_zip() {
+    let files = testing::random_files(15, 4, 20_000);
+    let archive = Archive::from_bytes(testing::build(&files, WriteOptions::default())).unwrap();
+    let zip = archive
+        .to_zip(Cursor::new(Vec::new()))
+        .unwrap()
+        .into_inner();
+
+    let converted = Archive::from_zip(Cursor::new(zip)).unwrap();
+    assert_eq!(converted.filenames(), archive.filenames());
+    for (name, data) in &files {
+        assert_eq!(&converted.get(name).unwrap().unwrap(), data);
+    }
+    // Zip times are only stored to two seconds, this one is even
+    let timestamp = UNIX_EPOCH + Duration::from_secs(DETERMINISTIC_TIMESTAMP.into());
+    assert_eq!(converted.timestamp(), Some(timestamp));
+    assert!(converted.check().is_ok());
+}